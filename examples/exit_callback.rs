@@ -1,4 +1,4 @@
-use process_terminal::{tprintln, with_exit_callback};
+use process_terminal::{tprintln, wait_terminal_closed, with_exit_callback};
 
 fn main() {
     with_exit_callback(|| {
@@ -7,7 +7,6 @@ fn main() {
 
     tprintln!("Press Ctrl+C to exit the program.");
 
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(1));
-    }
+    // Return from `main` once the terminal is closed.
+    wait_terminal_closed();
 }
@@ -1,6 +1,10 @@
-use process_terminal::{
-    add_process, tprintln, utils::create_printing_process, KeyCode, MessageSettings,
-    ProcessSettings, ScrollSettings,
+use {
+    process_terminal::{
+        add_process, tprintln,
+        utils::{create_printing_process, Spawn},
+        wait_terminal_closed, KeyCode, MessageSettings, ProcessSettings, ScrollSettings, Throttle,
+    },
+    std::time::Duration,
 };
 
 fn main() {
@@ -25,7 +29,27 @@ fn main() {
     )
     .unwrap();
 
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(1));
+    // A process printing millions of lines as fast as it can.
+    let process_flood = if cfg!(windows) {
+        Spawn::new("powershell").args(["-NoProfile", "-Command", "1..10000000"])
+    } else {
+        Spawn::new("seq").arg("10000000")
     }
+    .spawn()
+    .unwrap();
+
+    // Store at most 20 lines every 50ms, so the terminal stays responsive.
+    add_process(
+        "Flood",
+        process_flood,
+        ProcessSettings::builder()
+            .throttle(Throttle::Burst {
+                lines: 20,
+                interval: Duration::from_millis(50),
+            })
+            .build(),
+    )
+    .unwrap();
+
+    wait_terminal_closed();
 }
@@ -45,7 +45,7 @@ fn main() {
     sleep(Duration::from_secs(2));
 
     // Similar to print! and println!, but it prints messages in the `Main` section of the terminal.
-    // I don't find a way to capture the stdout (like println!) and display them into `Main` section.
+    // To show plain `println!` calls there too, enable `TerminalSettings::capture_stdout`.
     tprintln!("searching_message");
     // Block the current thread until the message is found.
     let msg = block_search_message("Foo", "llo").unwrap();
@@ -0,0 +1,1200 @@
+use {
+    crate::{
+        counters::Counters,
+        messages::{LogFile, Message, Messages, Style},
+        shared::Shared,
+        terminal::{
+            clear_pane, let_clone, spawn_thread, LineTap, Process, SearchHit, Searches,
+            SharedMessages, Stream, Subscribers, Taps,
+        },
+        CleanMode, LineTransform, MessageSettings, Throttle,
+    },
+    anyhow::{anyhow, Result},
+    std::{
+        borrow::Cow,
+        io::{self, BufRead, BufReader, Read},
+        process::Child,
+        sync::{mpsc::Receiver, LazyLock},
+        thread::{sleep, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+#[cfg(feature = "tui")]
+use ratatui::style::{Color, Modifier, Stylize};
+
+/// How long a restart waits for the output of the previous run to be closed.
+const RESTART_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Escape sequences of the captured lines, compiled the first time a line needs to be cleaned.
+pub(crate) static ANSI_REGEX: LazyLock<Regex> = LazyLock::new(Regex::new);
+
+/// Everything the capture threads of a process need, besides its buffers.
+pub(crate) fn capture(process: &Process, taps: &Taps) -> Result<Capture> {
+    if let Some(path) = &process.settings.log_to {
+        let log = LogFile::open(path)?;
+
+        for messages in [&process.out_messages, &process.err_messages] {
+            messages.write_with(|mut messages| messages.set_log(log.clone()));
+        }
+    }
+
+    let error_pattern = process
+        .settings
+        .error_pattern
+        .as_deref()
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|err| anyhow!("Invalid error pattern: {err}"))?;
+
+    let clear_pattern = match &process.settings.clear_regex {
+        CleanMode::Custom(pattern) => Some(
+            regex::Regex::new(pattern).map_err(|err| anyhow!("Invalid clear pattern: {err}"))?,
+        ),
+        CleanMode::Off | CleanMode::Ansi => None,
+    };
+
+    Ok(Capture {
+        process_name: process.name.clone(),
+        clear_regex: process.settings.clear_regex != CleanMode::Off,
+        clear_pattern,
+        #[cfg(feature = "tui")]
+        ansi_colors: process.settings.ansi_colors,
+        collapse_repeats: process.settings.collapse_repeats,
+        carriage_return: process.settings.carriage_return,
+        error_pattern,
+        transform: process.settings.transform.clone(),
+        max_line_length: process.settings.max_line_length,
+        #[cfg(feature = "json")]
+        json_lines: process.settings.json_lines,
+        subscribers: process.subscribers.clone(),
+        counters: process.counters.clone(),
+        line_tap: taps.line_tap.clone(),
+        merged: taps.merged.clone(),
+        #[cfg(feature = "tui")]
+        accent: process.accent,
+        throttle: process.settings.throttle,
+        throttled: Default::default(),
+    })
+}
+
+/// Start capturing the output of a child into the buffers of a process.
+pub(crate) fn spawn_capture(process: &Process, child: &mut Child, taps: &Taps) -> Result<()> {
+    let capture = capture(process, taps)?;
+
+    let threads = match &process.settings.messages {
+        MessageSettings::Output => {
+            let stdout = child.stdout.take().ok_or_else(|| {
+                anyhow::anyhow!("Failed to get stdout on process: {}", process.name)
+            })?;
+
+            let _out_messages = process.out_messages.clone();
+            let _searches = process.searches.clone();
+
+            vec![CaptureTask::Thread(spawn_thread!(thread_output(
+                stdout,
+                _out_messages,
+                _searches,
+                capture
+            )))]
+        }
+        MessageSettings::Error => {
+            let stderr = child.stderr.take().ok_or_else(|| {
+                anyhow::anyhow!("Failed to get stderr on process: {}", process.name)
+            })?;
+
+            let _err_messages = process.err_messages.clone();
+
+            vec![CaptureTask::Thread(spawn_thread!(thread_error(
+                stderr,
+                _err_messages,
+                None,
+                capture
+            )))]
+        }
+        MessageSettings::All | MessageSettings::Combined => {
+            let stdout = child.stdout.take().ok_or_else(|| {
+                anyhow::anyhow!("Failed to get stdout on process: {}", process.name)
+            })?;
+
+            let stderr = child.stderr.take().ok_or_else(|| {
+                anyhow::anyhow!("Failed to get stderr on process: {}", process.name)
+            })?;
+
+            let combined = process.settings.messages == MessageSettings::Combined;
+
+            let _out_messages = process.out_messages.clone();
+
+            // Combined processes interleave stderr lines into the stdout buffer.
+            let _err_messages = if combined {
+                process.out_messages.clone()
+            } else {
+                process.err_messages.clone()
+            };
+
+            let _searches = process.searches.clone();
+
+            // Searches look into the combined pane, so stderr lines can match too.
+            let _err_searches = combined.then(|| process.searches.clone());
+
+            let_clone!(capture, capture | _capture: Capture);
+
+            vec![
+                CaptureTask::Thread(spawn_thread!(thread_output(
+                    stdout,
+                    _out_messages,
+                    _searches,
+                    capture
+                ))),
+                CaptureTask::Thread(spawn_thread!(thread_error(
+                    stderr,
+                    _err_messages,
+                    _err_searches,
+                    _capture
+                ))),
+            ]
+        }
+        MessageSettings::None => vec![],
+    };
+
+    process.capture_threads.write_with(|mut capture_threads| {
+        *capture_threads = threads;
+    });
+
+    Ok(())
+}
+
+/// Start capturing the output of a tokio child into the buffers of a process, on tasks of
+/// `runtime`.
+#[cfg(feature = "tokio")]
+pub(crate) fn spawn_async_capture(
+    runtime: &tokio::runtime::Handle,
+    process: &Process,
+    child: &mut tokio::process::Child,
+    taps: &Taps,
+) -> Result<()> {
+    let capture = capture(process, taps)?;
+
+    let (read_stdout, read_stderr) = match &process.settings.messages {
+        MessageSettings::Output => (true, false),
+        MessageSettings::Error => (false, true),
+        MessageSettings::All | MessageSettings::Combined => (true, true),
+        MessageSettings::None => (false, false),
+    };
+
+    let stdout = read_stdout
+        .then(|| {
+            child
+                .stdout
+                .take()
+                .ok_or_else(|| anyhow!("Failed to get stdout on process: {}", process.name))
+        })
+        .transpose()?;
+
+    let stderr = read_stderr
+        .then(|| {
+            child
+                .stderr
+                .take()
+                .ok_or_else(|| anyhow!("Failed to get stderr on process: {}", process.name))
+        })
+        .transpose()?;
+
+    let combined = process.settings.messages == MessageSettings::Combined;
+    let mut tasks = vec![];
+
+    if let Some(stdout) = stdout {
+        tasks.push(CaptureTask::Task(runtime.spawn(task_lines(
+            stdout,
+            Stream::Out,
+            process.out_messages.clone(),
+            Some(process.searches.clone()),
+            capture.clone(),
+        ))));
+    }
+
+    if let Some(stderr) = stderr {
+        // Combined processes interleave stderr lines into the stdout buffer, matched by the
+        // searches too.
+        let (messages, searches) = if combined {
+            (process.out_messages.clone(), Some(process.searches.clone()))
+        } else {
+            (process.err_messages.clone(), None)
+        };
+
+        tasks.push(CaptureTask::Task(runtime.spawn(task_lines(
+            stderr,
+            Stream::Err,
+            messages,
+            searches,
+            capture,
+        ))));
+    }
+
+    process.capture_threads.write_with(|mut capture_threads| {
+        *capture_threads = tasks;
+    });
+
+    Ok(())
+}
+
+/// Start capturing the output of the new child of a process, once the capture of the previous
+/// one is over.
+pub(crate) fn restart_capture(
+    process: &Process,
+    child: &mut Child,
+    clear: bool,
+    taps: &Taps,
+    poll_interval: Duration,
+) -> Result<()> {
+    // Lines of the previous run must all be captured before the new ones.
+    let start = Instant::now();
+
+    while !process
+        .capture_threads
+        .read_access()
+        .iter()
+        .all(CaptureTask::is_finished)
+    {
+        if start.elapsed() > RESTART_TIMEOUT {
+            return Err(anyhow!(
+                "Output of the previous run of '{}' is still open.",
+                process.name
+            ));
+        }
+
+        sleep(poll_interval);
+    }
+
+    for (messages, scroll_status) in [
+        (&process.out_messages, &process.scroll_status_out),
+        (&process.err_messages, &process.scroll_status_err),
+    ] {
+        messages.write_with(|mut messages| messages.restart_clock());
+
+        if clear {
+            clear_pane(messages, scroll_status);
+        }
+    }
+
+    spawn_capture(process, child, taps)?;
+
+    process.stdin.write_with(|mut stdin| {
+        *stdin = child.stdin.take();
+    });
+
+    process.exit_status.write_with(|mut exit_status| {
+        *exit_status = None;
+    });
+
+    process.set_spawned(child.id());
+
+    Ok(())
+}
+
+/// Capture the lines of `stdout`, any reader the process writes its output to.
+pub(crate) fn thread_output<R: Read>(
+    stdout: R,
+    messages: SharedMessages,
+    searches: Shared<Searches>,
+    capture: Capture,
+) {
+    for line in capture.lines(stdout) {
+        let (line, replace) = match line {
+            Ok(line) => line,
+            Err(err) => return read_failed(&messages, Stream::Out, err),
+        };
+
+        capture_output_line(line, replace, &messages, &searches, &capture);
+    }
+
+    capture.finish(&messages, Stream::Out);
+}
+
+/// Capture the strings received from `lines`, each line of them being an output line, until
+/// every sender is dropped.
+pub(crate) fn thread_channel(
+    process_name: String,
+    lines: Receiver<String>,
+    messages: SharedMessages,
+    searches: Shared<Searches>,
+    capture: Capture,
+    main_messages: SharedMessages,
+) {
+    for received in lines {
+        for line in received.lines() {
+            capture_output_line(line.to_string(), false, &messages, &searches, &capture);
+        }
+    }
+
+    capture.finish(&messages, Stream::Out);
+
+    main_messages.write_with(|mut messages| {
+        messages.push(
+            Message::new(
+                format!("Process '{process_name}' ended: its channel was closed."),
+                Stream::Out,
+            )
+            .with_source(&process_name),
+        );
+    });
+}
+
+fn capture_output_line(
+    line: String,
+    replace: bool,
+    messages: &SharedMessages,
+    searches: &Shared<Searches>,
+    capture: &Capture,
+) {
+    let Some((line, styles)) = capture.parse(line) else {
+        return;
+    };
+
+    let index = capture.keep(messages, Stream::Out, &line, styles, replace);
+
+    match_search(searches, &line, index);
+}
+
+/// Capture the lines of `stderr`. With `searches`, they're matched by the searches too,
+/// as for a combined pane.
+pub(crate) fn thread_error<R: Read>(
+    stderr: R,
+    messages: SharedMessages,
+    searches: Option<Shared<Searches>>,
+    capture: Capture,
+) {
+    for line in capture.lines(stderr) {
+        let (line, replace) = match line {
+            Ok(line) => line,
+            Err(err) => return read_failed(&messages, Stream::Err, err),
+        };
+
+        let Some((line, styles)) = capture.parse(line) else {
+            continue;
+        };
+
+        let index = capture.keep(&messages, Stream::Err, &line, styles, replace);
+
+        if let Some(searches) = &searches {
+            match_search(searches, &line, index);
+        }
+    }
+
+    capture.finish(&messages, Stream::Err);
+}
+
+/// Capture the lines of `reader`, a pipe of a tokio child, like `thread_output` for `Stream::Out`
+/// and `thread_error` for `Stream::Err`, split and decoded like `CapturedLines`.
+#[cfg(feature = "tokio")]
+async fn task_lines<R>(
+    reader: R,
+    stream: Stream,
+    messages: SharedMessages,
+    searches: Option<Shared<Searches>>,
+    capture: Capture,
+) where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncBufReadExt;
+
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut splitter = capture.splitter();
+
+    loop {
+        let mut line = vec![];
+        let mut terminator = None;
+
+        while terminator.is_none() {
+            let buffer = match reader.fill_buf().await {
+                Ok(buffer) => buffer,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return read_failed(&messages, stream, err),
+            };
+
+            if buffer.is_empty() {
+                break;
+            }
+
+            let read;
+            (read, terminator) = splitter.read(buffer, &mut line);
+            reader.consume(read);
+        }
+
+        if terminator.is_none() && line.is_empty() {
+            return capture.finish(&messages, stream);
+        }
+
+        let Some((line, replace)) = splitter.finish(line, terminator) else {
+            continue;
+        };
+
+        let Some((line, styles)) = capture.parse(line) else {
+            continue;
+        };
+
+        let index = capture.keep(&messages, stream, &line, styles, replace);
+
+        if let Some(searches) = &searches {
+            match_search(searches, &line, index);
+        }
+    }
+}
+
+/// Report `line`, stored at `index`, to the running search, if it matches its pattern.
+fn match_search(searches: &Shared<Searches>, line: &str, index: usize) {
+    searches.read_with(|searches| {
+        for search in searches.iter() {
+            // Only the first line matching satisfies the search.
+            if search.hit.read_access().is_some() {
+                continue;
+            }
+
+            if let Some(line) = search.pattern.matches(line) {
+                *search.hit.write_access() = Some(SearchHit { index, line });
+            }
+        }
+    });
+}
+
+/// Note in the pane that the capture of `stream` stopped on a read error.
+fn read_failed(messages: &SharedMessages, stream: Stream, err: io::Error) {
+    let stream_name = match stream {
+        Stream::Out => "stdout",
+        Stream::Err => "stderr",
+    };
+
+    messages.write_with(|mut messages| {
+        messages.push(Message::new(
+            format!("Failed to read {stream_name}, capture stopped: {err}"),
+            Stream::Err,
+        ));
+    });
+}
+
+/// Thread capturing a stream of a process, or with the `tokio` feature a task of a runtime.
+pub(crate) enum CaptureTask {
+    Thread(JoinHandle<()>),
+    #[cfg(feature = "tokio")]
+    Task(tokio::task::JoinHandle<()>),
+}
+
+impl CaptureTask {
+    pub fn is_finished(&self) -> bool {
+        match self {
+            CaptureTask::Thread(thread) => thread.is_finished(),
+            #[cfg(feature = "tokio")]
+            CaptureTask::Task(task) => task.is_finished(),
+        }
+    }
+
+    /// The thread to join, if it's not a task.
+    pub fn thread(self) -> Option<JoinHandle<()>> {
+        match self {
+            CaptureTask::Thread(thread) => Some(thread),
+            #[cfg(feature = "tokio")]
+            CaptureTask::Task(_) => None,
+        }
+    }
+}
+
+/// Everything, besides the pane buffers, fed by the capture threads of a process.
+#[derive(Clone)]
+pub(crate) struct Capture {
+    pub process_name: String,
+    pub clear_regex: bool,
+    pub clear_pattern: Option<regex::Regex>,
+    #[cfg(feature = "tui")]
+    pub ansi_colors: bool,
+    pub collapse_repeats: bool,
+    pub carriage_return: bool,
+    pub error_pattern: Option<regex::Regex>,
+    pub transform: Option<LineTransform>,
+    pub max_line_length: Option<usize>,
+    #[cfg(feature = "json")]
+    pub json_lines: Option<crate::JsonLines>,
+    pub subscribers: Shared<Subscribers>,
+    pub counters: Shared<Counters>,
+    pub line_tap: Shared<LineTap>,
+    pub merged: SharedMessages,
+    /// Color of the name prefixing the lines of the merged timeline.
+    #[cfg(feature = "tui")]
+    pub accent: Color,
+    pub throttle: Throttle,
+    /// Lines let through by `throttle`, shared by the capture threads of the process.
+    throttled: Shared<Throttled>,
+}
+
+impl Capture {
+    /// Clean a captured line according to the process settings, returning the text along with
+    /// the styles of its color codes, or `None` when the transform drops it.
+    pub fn parse(&self, line: String) -> Option<(String, Vec<(usize, Style)>)> {
+        let (line, styles) = self.transform(line)?;
+
+        Some(match self.max_line_length {
+            Some(max_line_length) => truncate_line(line, styles, max_line_length),
+            None => (line, styles),
+        })
+    }
+
+    fn transform(&self, line: String) -> Option<(String, Vec<(usize, Style)>)> {
+        #[cfg(feature = "tui")]
+        let parsed = self.ansi_colors.then(|| ANSI_REGEX.parse(&line));
+        #[cfg(not(feature = "tui"))]
+        let parsed = None;
+
+        let (line, styles) = if let Some(parsed) = parsed {
+            parsed
+        } else if self.clear_regex {
+            (ANSI_REGEX.clear(line), vec![])
+        } else {
+            (ANSI_REGEX.clear_controls(line), vec![])
+        };
+
+        let (line, styles) = match &self.clear_pattern {
+            Some(clear_pattern) => match clear_pattern.replace_all(&line, "") {
+                // The styles start at offsets of the text before the matches were removed.
+                Cow::Owned(cleared) => (cleared, vec![]),
+                Cow::Borrowed(_) => (line, styles),
+            },
+            None => (line, styles),
+        };
+
+        #[cfg(all(feature = "json", feature = "tui"))]
+        let key_style = |json_lines: crate::JsonLines| json_lines.key_color.map(Style::from);
+        // Nothing draws the keys in a color without the TUI.
+        #[cfg(all(feature = "json", not(feature = "tui")))]
+        let key_style = |_| None;
+
+        #[cfg(feature = "json")]
+        let (line, styles) = match self
+            .json_lines
+            .and_then(|json_lines| crate::json::key_values(&line, key_style(json_lines)))
+        {
+            Some(key_values) => key_values,
+            None => (line, styles),
+        };
+
+        let Some(transform) = &self.transform else {
+            return Some((line, styles));
+        };
+
+        let transformed = transform.apply(line.clone())?;
+
+        // The offsets of the styles only hold for the original text.
+        if transformed == line {
+            Some((line, styles))
+        } else {
+            Some((transformed, vec![]))
+        }
+    }
+
+    fn lines<R: Read>(&self, reader: R) -> CapturedLines<R> {
+        CapturedLines {
+            reader: BufReader::new(reader),
+            splitter: self.splitter(),
+        }
+    }
+
+    fn splitter(&self) -> LineSplitter {
+        LineSplitter::new(self.carriage_return)
+    }
+
+    /// Store a parsed line in `messages`, then hand it to the subscribers, the line tap and the
+    /// merged timeline, returning its absolute position for the searches.
+    pub fn keep(
+        &self,
+        messages: &SharedMessages,
+        stream: Stream,
+        line: &str,
+        styles: Vec<(usize, Style)>,
+        replace: bool,
+    ) -> usize {
+        let (index, stored) = messages.write_with(|mut messages| {
+            let message = Message::new(line.to_string(), stream).with_styles(styles.clone());
+
+            self.store(&mut messages, message, replace)
+        });
+
+        self.record(stream, line);
+
+        // Lines overwritten in place, or dropped by the throttle, would flood the timeline.
+        if stored && !replace {
+            self.merge(stream, line, &styles);
+        }
+
+        index
+    }
+
+    /// Store a captured message in `messages`, overwriting the last one if `replace` is set, or
+    /// collapsing it into the last one if it's a repeat of it. Returns its absolute position
+    /// along with whether it was stored: for a line dropped by `throttle`, it's the position of
+    /// the next line stored.
+    fn store(&self, messages: &mut Messages, mut message: Message, replace: bool) -> (usize, bool) {
+        // Overwriting the last line doesn't grow the buffer.
+        if !replace && !self.admit(messages, message.stream) {
+            return (messages.end(), false);
+        }
+
+        if let Some(error_pattern) = &self.error_pattern {
+            message.error |= error_pattern.is_match(&message.text);
+        }
+
+        if replace {
+            messages.replace_last(message);
+        } else if !(self.collapse_repeats && messages.repeat_last(&message.text, message.stream)) {
+            messages.push(message);
+        }
+
+        (messages.end() - 1, true)
+    }
+
+    /// Whether the next line is stored according to `throttle`. A line ending a burst is preceded
+    /// by a note of the amount of lines dropped.
+    fn admit(&self, messages: &mut Messages, stream: Stream) -> bool {
+        self.throttled
+            .write_with(|mut throttled| match self.throttle {
+                Throttle::Off => true,
+                Throttle::Burst { lines, interval } => {
+                    if throttled
+                        .burst_start
+                        .is_none_or(|start| start.elapsed() >= interval)
+                    {
+                        throttled.note_dropped(messages, stream);
+
+                        *throttled = Throttled {
+                            burst_start: Some(Instant::now()),
+                            ..Default::default()
+                        };
+                    }
+
+                    if throttled.stored < lines {
+                        throttled.stored += 1;
+                        true
+                    } else {
+                        throttled.dropped += 1;
+                        false
+                    }
+                }
+                Throttle::Sample { every } => {
+                    throttled.seen += 1;
+
+                    (throttled.seen - 1) % every.max(1) == 0
+                }
+            })
+    }
+
+    /// Note the lines dropped by the current burst once the output of `stream` ended, as no line
+    /// follows to be preceded by the note.
+    pub fn finish(&self, messages: &SharedMessages, stream: Stream) {
+        // Locked in the same order as `admit`, called while storing a line.
+        messages.write_with(|mut messages| {
+            self.throttled
+                .write_with(|mut throttled| throttled.note_dropped(&mut messages, stream));
+        });
+    }
+
+    pub fn record(&self, stream: Stream, line: &str) {
+        self.counters
+            .write_with(|mut counters| counters.record_line(line.len()));
+
+        // Forward the line to every subscriber, dropping the ones whose receiver is gone.
+        self.subscribers.write_with(|mut subscribers| {
+            subscribers.retain(|subscriber| subscriber.send(line.to_string()).is_ok());
+        });
+
+        if let Some(line_tap) = self.line_tap.read_access().as_ref() {
+            line_tap(&self.process_name, stream, line);
+        }
+    }
+
+    /// Append `line` to the merged timeline, prefixed by the name of the process in its accent.
+    pub fn merge(&self, stream: Stream, line: &str, styles: &[(usize, Style)]) {
+        let prefix = format!("{} | ", self.process_name);
+
+        #[cfg(feature = "tui")]
+        let mut prefixed = vec![
+            (0, Style::default().fg(self.accent).bold()),
+            (prefix.len(), Style::default()),
+        ];
+        // Without the TUI, nothing draws the name in the accent.
+        #[cfg(not(feature = "tui"))]
+        let mut prefixed = vec![];
+
+        prefixed.extend(
+            styles
+                .iter()
+                .map(|&(offset, style)| (offset + prefix.len(), style)),
+        );
+
+        let mut message = Message::new(format!("{prefix}{line}"), stream)
+            .with_styles(prefixed)
+            .with_source(&self.process_name);
+
+        if let Some(error_pattern) = &self.error_pattern {
+            message.error |= error_pattern.is_match(line);
+        }
+
+        self.merged.write_with(|mut merged| merged.push(message));
+    }
+}
+
+/// Keep the first `max_chars` characters of `line` followed by `…`, along with the styles
+/// starting in them.
+fn truncate_line(
+    mut line: String,
+    mut styles: Vec<(usize, Style)>,
+    max_chars: usize,
+) -> (String, Vec<(usize, Style)>) {
+    if let Some((end, _)) = line.char_indices().nth(max_chars) {
+        line.truncate(end);
+        line.push('…');
+        styles.retain(|(offset, _)| *offset < end);
+    }
+
+    (line, styles)
+}
+
+/// Lines seen by `Capture::admit`.
+#[derive(Default)]
+struct Throttled {
+    burst_start: Option<Instant>,
+    stored: usize,
+    dropped: usize,
+    seen: usize,
+}
+
+impl Throttled {
+    fn note_dropped(&mut self, messages: &mut Messages, stream: Stream) {
+        if self.dropped > 0 {
+            messages.push(Message::new(
+                format!("({} lines dropped)", self.dropped),
+                stream,
+            ));
+        }
+
+        self.dropped = 0;
+    }
+}
+
+/// Lines read from the output of a process, split on `\n` and, with
+/// `ProcessSettings::carriage_return`, on `\r` too.
+///
+/// Each line comes along with whether it overwrites the previous one, which ended with a `\r`.
+struct CapturedLines<R> {
+    reader: BufReader<R>,
+    splitter: LineSplitter,
+}
+
+impl<R: Read> CapturedLines<R> {
+    /// Read up to the next line terminator, returning it, or `None` at the end of the output.
+    fn read_line(&mut self, line: &mut Vec<u8>) -> io::Result<Option<u8>> {
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+
+            if buffer.is_empty() {
+                return Ok(None);
+            }
+
+            let (read, terminator) = self.splitter.read(buffer, line);
+            self.reader.consume(read);
+
+            if terminator.is_some() {
+                return Ok(terminator);
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for CapturedLines<R> {
+    type Item = io::Result<(String, bool)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = vec![];
+
+            let terminator = match self.read_line(&mut line) {
+                Ok(terminator) => terminator,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if terminator.is_none() && line.is_empty() {
+                return None;
+            }
+
+            if let Some(line) = self.splitter.finish(line, terminator) {
+                return Some(Ok(line));
+            }
+        }
+    }
+}
+
+/// Splitting of the output of a process into lines, for `CapturedLines` and `task_lines`.
+struct LineSplitter {
+    carriage_return: bool,
+    after_carriage_return: bool,
+}
+
+impl LineSplitter {
+    fn new(carriage_return: bool) -> Self {
+        LineSplitter {
+            carriage_return,
+            after_carriage_return: false,
+        }
+    }
+
+    /// Append the bytes of `buffer` up to the next line terminator to `line`, returning how many
+    /// were read, terminator included, and the terminator if one was found.
+    fn read(&self, buffer: &[u8], line: &mut Vec<u8>) -> (usize, Option<u8>) {
+        let end = buffer
+            .iter()
+            .position(|&byte| byte == b'\n' || (self.carriage_return && byte == b'\r'));
+
+        match end {
+            Some(end) => {
+                line.extend_from_slice(&buffer[..end]);
+
+                (end + 1, Some(buffer[end]))
+            }
+            None => {
+                line.extend_from_slice(buffer);
+
+                (buffer.len(), None)
+            }
+        }
+    }
+
+    /// Line read up to `terminator`, along with whether it overwrites the previous one, or `None`
+    /// if there's nothing to show.
+    fn finish(&mut self, mut line: Vec<u8>, terminator: Option<u8>) -> Option<(String, bool)> {
+        let replace = self.after_carriage_return;
+        let carriage_return = terminator == Some(b'\r');
+
+        // A `\r` without anything after it has nothing to overwrite the line with, like the `\n`
+        // of a `\r\n`.
+        if line.is_empty() && (replace || carriage_return) {
+            self.after_carriage_return = replace && carriage_return;
+            return None;
+        }
+
+        self.after_carriage_return = carriage_return;
+
+        if !self.carriage_return && line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        // Invalid bytes are shown as replacement characters rather than dropping the line.
+        Some((String::from_utf8_lossy(&line).into_owned(), replace))
+    }
+}
+
+pub(crate) struct Regex(regex::Regex);
+
+impl Regex {
+    pub fn new() -> Self {
+        Self(regex::Regex::new(r"\x1b\[([\x30-\x3f]*[\x20-\x2f]*[\x40-\x7e])").unwrap())
+    }
+
+    pub fn clear(&self, line: String) -> String {
+        self.0.replace_all(&line, "").to_string()
+    }
+
+    /// Strip the escape sequences other than SGR, e.g. moving the cursor or erasing, which
+    /// would corrupt the pane, keeping the colors.
+    pub fn clear_controls(&self, line: String) -> String {
+        if !line.contains('\x1b') {
+            return line;
+        }
+
+        self.0
+            .replace_all(&line, |captures: &regex::Captures| {
+                if captures[1].ends_with('m') {
+                    captures[0].to_string()
+                } else {
+                    String::new()
+                }
+            })
+            .to_string()
+    }
+
+    /// Strip every escape sequence, turning the SGR ones into styles starting at the byte
+    /// offset they were found at in the stripped text.
+    #[cfg(feature = "tui")]
+    pub fn parse(&self, line: &str) -> (String, Vec<(usize, Style)>) {
+        let mut text = String::with_capacity(line.len());
+        let mut styles: Vec<(usize, Style)> = vec![];
+        let mut style = Style::default();
+        let mut last = 0;
+
+        for captures in self.0.captures_iter(line) {
+            let sequence = captures.get(0).unwrap();
+
+            text.push_str(&line[last..sequence.start()]);
+            last = sequence.end();
+
+            let Some(params) = captures[1].strip_suffix('m') else {
+                continue;
+            };
+
+            style = apply_sgr(style, params);
+
+            // Only the latest style counts when several sequences are found at the same offset.
+            if let Some((offset, last_style)) = styles.last_mut() {
+                if *offset == text.len() {
+                    *last_style = style;
+                    continue;
+                }
+            }
+
+            styles.push((text.len(), style));
+        }
+
+        text.push_str(&line[last..]);
+
+        (text, styles)
+    }
+}
+
+#[cfg(feature = "tui")]
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            _ => style,
+        };
+    }
+
+    style
+}
+
+/// Read the `5;n` or `2;r;g;b` arguments following a `38` or `48` SGR code.
+#[cfg(feature = "tui")]
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()?)),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::ProcessSettings, std::sync::mpsc::channel};
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn sgr_codes_become_styles() {
+        let (text, styles) =
+            Regex::new().parse("\x1b[1;31mbold red\x1b[22m red\x1b[0m \x1b[2Kplain\x1b[m");
+
+        let red = Style::default().fg(Color::Indexed(1));
+
+        assert_eq!(text, "bold red red plain");
+        assert_eq!(
+            styles,
+            [
+                (0, red.add_modifier(Modifier::BOLD)),
+                (8, red.remove_modifier(Modifier::BOLD | Modifier::DIM)),
+                (12, Style::default()),
+                (18, Style::default()),
+            ]
+        );
+
+        assert_eq!(
+            apply_sgr(Style::default(), "38;5;208;48;2;1;2;3"),
+            Style::default()
+                .fg(Color::Indexed(208))
+                .bg(Color::Rgb(1, 2, 3))
+        );
+        assert_eq!(
+            apply_sgr(Style::default(), "94;101"),
+            Style::default()
+                .fg(Color::Indexed(12))
+                .bg(Color::Indexed(9))
+        );
+
+        // A truncated extended color is ignored.
+        assert_eq!(apply_sgr(Style::default(), "38;2;1"), Style::default());
+    }
+
+    fn captured_lines(output: &str, carriage_return: bool) -> Vec<(String, bool)> {
+        CapturedLines {
+            reader: BufReader::new(output.as_bytes()),
+            splitter: LineSplitter::new(carriage_return),
+        }
+        .collect::<io::Result<_>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn carriage_returns_split_the_lines() {
+        let output = "10%\r50%\r100%\ndone\r\n\rnext";
+
+        assert_eq!(
+            captured_lines(output, true),
+            [
+                ("10%".to_string(), false),
+                ("50%".to_string(), true),
+                ("100%".to_string(), true),
+                ("done".to_string(), false),
+                ("next".to_string(), false),
+            ]
+        );
+
+        // Without the setting, only the `\r` of a `\r\n` is dropped.
+        let lines = captured_lines(output, false)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, ["10%\r50%\r100%", "done", "\rnext"]);
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn long_lines_are_truncated() {
+        let bold = Style::default().bold();
+
+        assert_eq!(
+            truncate_line("héllo world".to_string(), vec![(0, bold), (7, bold)], 5),
+            ("héllo…".to_string(), vec![(0, bold)])
+        );
+        assert_eq!(
+            truncate_line("short".to_string(), vec![], 5),
+            ("short".to_string(), vec![])
+        );
+
+        // Applied after the escape sequences are stripped.
+        let settings = ProcessSettings::builder()
+            .clear_regex(CleanMode::Ansi)
+            .max_line_length(3)
+            .build();
+        let process = Process::new("Truncated".to_string(), settings);
+        let capture = capture(&process, &Taps::default()).unwrap();
+
+        assert_eq!(
+            capture.parse("\x1b[31mabcdef".to_string()),
+            Some(("abc…".to_string(), vec![]))
+        );
+    }
+
+    #[test]
+    fn throttled_lines_are_still_forwarded() {
+        let settings = ProcessSettings::builder()
+            .throttle(Throttle::Sample { every: 2 })
+            .build();
+        let process = Process::new("Throttled".to_string(), settings);
+        let taps = Taps::default();
+        let capture = capture(&process, &taps).unwrap();
+
+        let (sender, receiver) = channel();
+        process
+            .subscribers
+            .write_with(|mut subscribers| subscribers.push(sender));
+
+        for line in 0..10 {
+            capture.keep(
+                &process.out_messages,
+                Stream::Out,
+                &line.to_string(),
+                vec![],
+                false,
+            );
+        }
+
+        assert_eq!(process.out_messages.read_access().len(), 5);
+        assert_eq!(receiver.try_iter().count(), 10);
+
+        // The merged timeline only shows the lines stored.
+        assert_eq!(taps.merged.read_access().len(), 5);
+    }
+
+    #[test]
+    fn burst_drops_are_noted_once_the_output_ends() {
+        let settings = ProcessSettings::builder()
+            .throttle(Throttle::Burst {
+                lines: 2,
+                interval: Duration::from_secs(60),
+            })
+            .build();
+        let process = Process::new("Burst".to_string(), settings);
+        let capture = capture(&process, &Taps::default()).unwrap();
+
+        for line in 0..5 {
+            capture.keep(
+                &process.out_messages,
+                Stream::Out,
+                &line.to_string(),
+                vec![],
+                false,
+            );
+        }
+
+        let texts = || {
+            process
+                .out_messages
+                .read_access()
+                .iter()
+                .map(|message| message.text.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Without a line after the burst, the drops are only noted at the end of the output.
+        assert_eq!(texts(), ["0", "1"]);
+
+        capture.finish(&process.out_messages, Stream::Out);
+
+        assert_eq!(texts(), ["0", "1", "(3 lines dropped)"]);
+
+        // Noted once.
+        capture.finish(&process.out_messages, Stream::Out);
+
+        assert_eq!(texts().len(), 3);
+    }
+
+    #[test]
+    fn clear_controls_keeps_colors() {
+        let line = "\x1b[2K\x1b[1G\x1b[31mred\x1b[0m \x1b[3Adone".to_string();
+
+        assert_eq!(
+            ANSI_REGEX.clear_controls(line.clone()),
+            "\x1b[31mred\x1b[0m done"
+        );
+        assert_eq!(ANSI_REGEX.clear(line), "red done");
+    }
+}
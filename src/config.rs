@@ -0,0 +1,252 @@
+use {
+    crate::{CleanMode, PaneLayout, TerminalSettings},
+    anyhow::{bail, Context, Result},
+    regex::Regex,
+    std::{fs, path::Path},
+};
+
+/// Read the settings of the terminal from the configuration file at `path`.
+pub(crate) fn read_settings(path: &Path) -> Result<TerminalSettings> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the configuration '{}'", path.display()))?;
+
+    parse_settings(&text).with_context(|| format!("Invalid configuration '{}'", path.display()))
+}
+
+/// Settings written in the TOML syntax, as deserialized by `TerminalSettings`. Unset keys keep
+/// their default.
+pub(crate) fn parse_settings(text: &str) -> Result<TerminalSettings> {
+    let settings = toml::from_str(text)?;
+
+    validate(&settings)?;
+
+    Ok(settings)
+}
+
+/// Reject the values that deserialize but the terminal can't work with, naming their key.
+fn validate(settings: &TerminalSettings) -> Result<()> {
+    let process = &settings.process_defaults;
+
+    for (key, zero) in [
+        ("refresh_ms", settings.refresh.is_zero()),
+        ("poll_interval_ms", settings.poll_interval.is_zero()),
+        ("max_main_lines", settings.max_main_lines == Some(0)),
+        (
+            "layout.grid.cols",
+            settings.layout == PaneLayout::Grid { cols: 0 },
+        ),
+        ("process.max_lines", process.max_lines == Some(0)),
+    ] {
+        if zero {
+            bail!("'{key}' must be at least 1");
+        }
+    }
+
+    let clear_pattern = match &process.clear_regex {
+        CleanMode::Custom(pattern) => Some(pattern),
+        _ => None,
+    };
+
+    for (key, pattern) in [
+        ("process.error_pattern", process.error_pattern.as_ref()),
+        ("process.clear_regex.custom", clear_pattern),
+    ] {
+        if let Some(pattern) = pattern {
+            Regex::new(pattern).with_context(|| format!("'{key}' is not a valid regex"))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tui")]
+    use {
+        crate::{Border, KeyAction, OnExit, Timestamps},
+        crossterm::event::{KeyCode, KeyModifiers},
+        ratatui::{layout::Alignment, style::Color},
+        std::time::Duration,
+    };
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn config_sets_the_terminal_settings() {
+        let settings = parse_settings(
+            r#"
+            # Laid out in a grid.
+            layout = { grid = { cols = 3 } }
+            refresh_ms = 100
+            quit_key = "q"
+            quit_modifiers = "Ctrl"
+
+            [theme]
+            border = "rounded"
+            "#,
+        )
+        .unwrap();
+
+        assert!(settings.layout == PaneLayout::Grid { cols: 3 });
+        assert_eq!(settings.refresh, Duration::from_millis(100));
+        assert!(settings.theme.border == Border::Rounded);
+        assert_eq!(settings.quit_key, KeyCode::Char('q'));
+        assert_eq!(settings.quit_modifiers, KeyModifiers::CONTROL);
+
+        let Err(err) = parse_settings("mouse = true\nlayout = \"diagonal\"") else {
+            panic!("The layout is invalid.");
+        };
+
+        let err = format!("{err:#}");
+
+        assert!(err.contains("line 2"), "{err}");
+        assert!(
+            err.contains("unknown variant `diagonal`, expected one of"),
+            "{err}"
+        );
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn process_table_sets_the_process_defaults() {
+        let settings = parse_settings(
+            r#"
+            [process]
+            max_lines = 1_000
+            timestamps = "elapsed"
+            wrap = false
+            color = "cyan"
+            error_pattern = '\bERROR\b' # Literal, so not unescaped.
+            on_exit = "remove"
+            "#,
+        )
+        .unwrap();
+
+        let process = &settings.process_defaults;
+
+        assert_eq!(process.max_lines, Some(1_000));
+        assert!(process.timestamps == Timestamps::ElapsedSinceStart);
+        assert!(!process.wrap);
+        assert_eq!(process.color, Some(Color::Cyan));
+        assert_eq!(process.error_pattern.as_deref(), Some(r"\bERROR\b"));
+        assert!(process.on_exit == OnExit::Remove(Duration::ZERO));
+
+        let settings = parse_settings("[process]\non_exit = { remove_after_ms = 5_000 }").unwrap();
+
+        assert!(settings.process_defaults.on_exit == OnExit::Remove(Duration::from_secs(5)));
+
+        let Err(err) = parse_settings("[process]\nerror_pattern = '('") else {
+            panic!("The pattern is invalid.");
+        };
+
+        assert!(format!("{err:#}").starts_with("'process.error_pattern' is not a valid regex"));
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn keys_table_rebinds_the_actions() {
+        let settings = parse_settings(
+            r#"
+            [keys]
+            copy = "c"
+            next_error = [
+                "n",   # Kept.
+                "Alt+Down",
+            ]
+            previous_error = "E"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings.key_bindings,
+            [
+                (KeyAction::Copy, KeyCode::Char('c'), KeyModifiers::empty()),
+                (
+                    KeyAction::NextError,
+                    KeyCode::Char('n'),
+                    KeyModifiers::empty()
+                ),
+                (KeyAction::NextError, KeyCode::Down, KeyModifiers::ALT),
+                (
+                    KeyAction::PreviousError,
+                    KeyCode::Char('E'),
+                    KeyModifiers::SHIFT
+                ),
+            ]
+        );
+
+        for (text, error) in [
+            ("[keys]\njump = \"j\"", "unknown variant `jump`"),
+            ("[keys]\ncopy = \"Hyper+c\"", "Unknown key 'Hyper+c'."),
+        ] {
+            let Err(err) = parse_settings(text) else {
+                panic!("'{text}' is invalid.");
+            };
+
+            let err = format!("{err:#}");
+
+            assert!(err.contains("line 2") && err.contains(error), "{err}");
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn inline_tables_and_dotted_keys_are_expanded() {
+        let settings = parse_settings(
+            r#"
+            theme = { border = "double", name = "center" }
+            process.wrap = false
+            keys = { copy = "Ctrl+x" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(settings.theme.border == Border::Double);
+        assert!(settings.theme.name == Alignment::Center);
+        assert!(!settings.process_defaults.wrap);
+        assert_eq!(
+            settings.key_bindings,
+            [(KeyAction::Copy, KeyCode::Char('x'), KeyModifiers::CONTROL)]
+        );
+    }
+
+    #[test]
+    fn invalid_values_name_their_key() {
+        for (text, error) in [
+            ("refresh_ms = 0", "'refresh_ms' must be at least 1"),
+            (
+                "[process]\nmax_lines = 0",
+                "'process.max_lines' must be at least 1",
+            ),
+            (
+                "layout = { grid = { cols = 0 } }",
+                "'layout.grid.cols' must be at least 1",
+            ),
+        ] {
+            let Err(err) = parse_settings(text) else {
+                panic!("'{text}' is invalid.");
+            };
+
+            assert_eq!(format!("{err:#}"), error);
+        }
+
+        for (text, error) in [
+            ("mouse = true false", "line 1"),
+            ("refresh_ms = -1", "refresh_ms"),
+            ("[process]\nmax_lines = \"all\"", "max_lines"),
+            ("[tabs]\nfoo = 1", "unknown field `tabs`"),
+            #[cfg(feature = "tui")]
+            ("[theme]\ncolor = \"red\"", "unknown field `color`"),
+        ] {
+            let Err(err) = parse_settings(text) else {
+                panic!("'{text}' is invalid.");
+            };
+
+            let err = format!("{err:#}");
+
+            assert!(err.contains(error), "{text}: {err}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Window over which the output rate is averaged.
+pub const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Lines captured from a process, bucketed by second since the last reset.
+pub struct Counters {
+    since: Instant,
+    buckets: VecDeque<(u64, u64)>,
+    bytes: u64,
+}
+
+/// Snapshot of the counters of a process, shown in the title of its panes.
+#[cfg(feature = "tui")]
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    /// Lines per second, as returned by `Counters::rate`.
+    pub rate: f64,
+    /// Bytes captured since the last reset.
+    pub bytes: u64,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            since: Instant::now(),
+            buckets: VecDeque::new(),
+            bytes: 0,
+        }
+    }
+}
+
+impl Counters {
+    /// Count a captured line of `bytes` bytes, its terminator excluded.
+    pub fn record_line(&mut self, bytes: usize) {
+        self.bytes += bytes as u64 + 1;
+
+        let second = self.since.elapsed().as_secs();
+
+        match self.buckets.back_mut() {
+            Some((bucket, count)) if *bucket == second => *count += 1,
+            _ => self.buckets.push_back((second, 1)),
+        }
+
+        let first = second.saturating_sub(RATE_WINDOW.as_secs());
+
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(bucket, _)| *bucket < first)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Lines per second over the last `RATE_WINDOW` whole seconds plus the current partial one,
+    /// or since the last reset if more recent.
+    pub fn rate(&self) -> f64 {
+        let elapsed = self.since.elapsed();
+        let first = elapsed.as_secs().saturating_sub(RATE_WINDOW.as_secs());
+
+        let lines = self
+            .buckets
+            .iter()
+            .filter(|(bucket, _)| *bucket >= first)
+            .map(|(_, count)| count)
+            .sum::<u64>();
+
+        let span = elapsed - Duration::from_secs(first);
+
+        if span.is_zero() {
+            0.0
+        } else {
+            lines as f64 / span.as_secs_f64()
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn stats(&self) -> Stats {
+        Stats {
+            rate: self.rate(),
+            bytes: self.bytes,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,51 @@
+use {
+    crate::{shared::Shared, SearchHit},
+    std::{
+        process::ExitStatus,
+        sync::mpsc::{channel, Receiver, Sender},
+    },
+};
+
+/// What happened in the terminal, sent to the receivers returned by `subscribe`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TerminalEvent {
+    /// A process was added, or reattached.
+    ProcessAdded(String),
+    /// A process was removed, or detached.
+    ProcessRemoved(String),
+    /// The child of a process exited, with its status unless waiting for it failed.
+    ProcessExited {
+        name: String,
+        status: Option<ExitStatus>,
+    },
+    /// The child of a process was spawned again by its `RestartPolicy`.
+    ProcessRestarted(String),
+    /// The pane in full screen changed, along with the name of its process, as returned by
+    /// `current_focus`.
+    FocusChanged(Option<String>),
+    /// A blocking search found a line of a process.
+    SearchFound { process: String, hit: SearchHit },
+    /// The terminal stopped on an error it can't recover from, e.g. failing to read the keys,
+    /// and was closed.
+    Failed(String),
+}
+
+/// Senders of the subscribers of a terminal, dropped once their receiver is.
+#[derive(Clone, Default)]
+pub(crate) struct Events(Shared<Vec<Sender<TerminalEvent>>>);
+
+impl Events {
+    pub fn subscribe(&self) -> Receiver<TerminalEvent> {
+        let (sender, receiver) = channel();
+
+        self.0.write_with(|mut senders| senders.push(sender));
+
+        receiver
+    }
+
+    pub fn emit(&self, event: TerminalEvent) {
+        self.0.write_with(|mut senders| {
+            senders.retain(|sender| sender.send(event.clone()).is_ok());
+        });
+    }
+}
@@ -1,25 +1,255 @@
 use {
-    crate::{ProcessSettings, TERMINAL},
+    crate::{
+        ProcessHandle, ProcessInfo, ProcessSettings, ProcessSettingsBuilder, ScrollStatus,
+        SearchHit, Stream, Terminal, TerminalEvent, TerminalSettings, TERMINAL,
+    },
     anyhow::Result,
-    std::process::Child,
+    std::{
+        io::Read,
+        process::{Child, Command, ExitStatus},
+        sync::mpsc::Receiver,
+        time::Duration,
+    },
+};
+
+#[cfg(feature = "tui")]
+use {
+    crossterm::event::{KeyCode, KeyModifiers},
+    ratatui::text::Line,
 };
 
 #[macro_export]
 /// Print a message in the Main section of the teminal.
 macro_rules! tprintln {
     ($($arg:tt)*) => {
-        if cfg!(debug_assertions) {
-            process_terminal::TERMINAL.add_message(format!($($arg)*));
-        }
+        process_terminal::TERMINAL.add_message(format!($($arg)*));
+    };
+}
+
+#[macro_export]
+/// Print a message in the Main section tagged with a source, e.g. `tsprintln!("db", "{n} rows")`,
+/// to show it alone with `s` or `set_source_filter`.
+macro_rules! tsprintln {
+    ($source:expr, $($arg:tt)*) => {
+        process_terminal::TERMINAL.add_source_message($source, format!($($arg)*));
+    };
+}
+
+#[macro_export]
+/// Print an error message in the Main section of the teminal, highlighted in red.
+macro_rules! teprintln {
+    ($($arg:tt)*) => {
+        process_terminal::TERMINAL.add_error_message(format!($($arg)*));
+    };
+}
+
+#[macro_export]
+/// Print a debug message in the Main section, in dark gray.
+macro_rules! tdebug {
+    ($($arg:tt)*) => {
+        process_terminal::TERMINAL.add_level_message(process_terminal::Level::Debug, format!($($arg)*));
+    };
+}
+
+#[macro_export]
+/// Print an info message in the Main section, in gray.
+macro_rules! tinfo {
+    ($($arg:tt)*) => {
+        process_terminal::TERMINAL.add_level_message(process_terminal::Level::Info, format!($($arg)*));
+    };
+}
+
+#[macro_export]
+/// Print a warning in the Main section, in yellow.
+macro_rules! twarn {
+    ($($arg:tt)*) => {
+        process_terminal::TERMINAL.add_level_message(process_terminal::Level::Warn, format!($($arg)*));
+    };
+}
+
+#[macro_export]
+/// Print an error in the Main section, in red.
+macro_rules! terror {
+    ($($arg:tt)*) => {
+        process_terminal::TERMINAL.add_level_message(process_terminal::Level::Error, format!($($arg)*));
     };
 }
 
-/// Add a process to the terminal.
-pub fn add_process(name: &str, child: Child, settings: ProcessSettings) -> Result<()> {
+/// Initialize the terminal with custom settings, e.g. the layout, the refresh rate, the quit key
+/// or the lines kept in the Main section.
+/// Must be called before any other function, otherwise the terminal is implicitly initialized
+/// with the default settings and an error is returned.
+pub fn init_terminal(settings: TerminalSettings) -> Result<()> {
+    Terminal::init(settings)
+}
+
+/// Like `init_terminal`, with the settings read from the configuration file at `path`, written
+/// in the TOML syntax, e.g.:
+///
+/// ```toml
+/// layout = { grid = { cols = 3 } }
+/// refresh_ms = 100
+/// status_bar = true
+/// quit_key = "q"
+/// quit_modifiers = "Ctrl"
+///
+/// [theme]
+/// border = "rounded"
+/// name = "left"
+///
+/// [process]
+/// timestamps = "elapsed"
+/// error_pattern = '\bERROR\b'
+/// on_exit = "remove"
+///
+/// [keys]
+/// next_error = ["n", "Alt+Down"]
+/// ```
+///
+/// The keys are the serde names of `TerminalSettings`, the ones of `[process]` of
+/// `ProcessSettings` and the ones of `[keys]` of the variants of `KeyAction` (e.g. `next_error`),
+/// the durations being in milliseconds. Unset keys keep their default. Fails on the unknown keys
+/// and on the invalid values, naming their key.
+#[cfg(feature = "config")]
+pub fn init_terminal_from_file<P: AsRef<std::path::Path>>(path: P) -> Result<()> {
+    Terminal::init(crate::config::read_settings(path.as_ref())?)
+}
+
+/// Builder of `ProcessSettings` starting from `TerminalSettings::process_defaults`, e.g. the
+/// `[process]` table of the configuration file, instead of the defaults of the crate.
+pub fn process_settings() -> ProcessSettingsBuilder {
+    TERMINAL.process_settings()
+}
+
+/// Add a process to the terminal, returning a handle to it.
+/// Fails if a process with the same name is already shown.
+pub fn add_process(name: &str, child: Child, settings: ProcessSettings) -> Result<ProcessHandle> {
     TERMINAL.add_process(name, child, settings)
 }
 
+/// Add a process spawned by `tokio::process::Command`, like `add_process`, reading its output on
+/// tasks of the current tokio runtime, from which it must be called. Its exit is reported, but it
+/// isn't restarted, nor written to or stopped by the terminal: spawn it with `kill_on_drop` to
+/// stop it along with the runtime. Fails if a process with the same name is already shown.
+#[cfg(feature = "tokio")]
+pub fn add_process_async(
+    name: &str,
+    child: tokio::process::Child,
+    settings: ProcessSettings,
+) -> Result<ProcessHandle> {
+    TERMINAL.add_process_async(name, child, settings)
+}
+
+/// Add a pane showing the lines read from `reader`, e.g. a socket or a file, as the output of a
+/// process, or as its errors with `MessageSettings::Error`. There's no child, so no exit is
+/// reported. Fails if a process with the same name is already shown.
+pub fn add_reader<R>(name: &str, reader: R, settings: ProcessSettings) -> Result<ProcessHandle>
+where
+    R: Read + Send + 'static,
+{
+    TERMINAL.add_reader(name, reader, settings)
+}
+
+/// Add a pane showing the strings received from `lines`, each line of them being an output line
+/// of a process. A note is printed in the Main section once every sender is dropped.
+/// Fails if a process with the same name is already shown.
+pub fn add_channel(
+    name: &str,
+    lines: Receiver<String>,
+    settings: ProcessSettings,
+) -> Result<ProcessHandle> {
+    TERMINAL.add_channel(name, lines, settings)
+}
+
+/// Exit status of a process, `None` while it's running.
+pub fn process_exit_status<P>(process: P) -> Result<Option<ExitStatus>>
+where
+    P: ToString,
+{
+    TERMINAL.process_exit_status(process)
+}
+
+/// Send `signal` (e.g. `libc::SIGHUP`) to the child of a process.
+#[cfg(unix)]
+pub fn signal_process<P>(process: P, signal: i32) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.signal_process(process, signal)
+}
+
+/// Ask the child of a process to terminate: SIGTERM on Unix, `TerminateProcess` on Windows.
+/// Its exit is reported as usual.
+pub fn terminate_process<P>(process: P) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.terminate_process(process)
+}
+
+/// PID, start time and state of the child of a process, e.g. to signal it from outside.
+pub fn process_info<P>(process: P) -> Result<ProcessInfo>
+where
+    P: ToString,
+{
+    TERMINAL.process_info(process)
+}
+
+/// Blocking function that waits for a process to exit, returning its exit status.
+pub fn wait_process_exit<P>(process: P) -> Result<ExitStatus>
+where
+    P: ToString,
+{
+    TERMINAL.wait_process_exit(process)
+}
+
+/// Write a line to the stdin of a process.
+/// The child must have been spawned with a piped stdin and must still be running.
+pub fn send_input<P>(process: P, data: &str) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.send_input(process, data)
+}
+
+/// Replace a finished process with a new child, keeping its panes and key bindings.
+/// If `clear` is true, the output of the previous run is removed.
+///
+/// Fails if the previous child is still running. Blocks until the output of the previous run
+/// is fully captured, so the lines of the two runs never interleave.
+pub fn restart_process<P>(process: P, child: Child, clear: bool) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.restart_process(process, child, clear)
+}
+
+/// Remove a process from the terminal, freeing its panes and key bindings.
+/// The process keeps running, and its exit is still reported in the Main section.
+pub fn remove_process<P>(process: P) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.remove_process(process)
+}
+
+/// Stop showing a process, removing its panes and key bindings, and hand it back to the caller.
+/// The process keeps running and its output keeps being captured in the background.
+pub fn detach_process<P>(process: P) -> Result<ProcessHandle>
+where
+    P: ToString,
+{
+    TERMINAL.detach_process(process)
+}
+
+/// Show again a process previously detached with `detach_process`.
+pub fn reattach_process(handle: ProcessHandle) -> Result<()> {
+    TERMINAL.reattach_process(handle)
+}
+
 /// Blocking function that block the current thread, searching for a substring in a specific process output, returning the whole output message.
+/// Lines captured before the call are searched too. Returns an error once the output of the
+/// process is over without a match, unless it's restarted by its `RestartPolicy`.
 pub fn block_search_message<S, P>(process: P, submsg: S) -> Result<String>
 where
     S: ToString,
@@ -28,10 +258,311 @@ where
     TERMINAL.block_search_message(process, submsg)
 }
 
+/// Like `block_search_message`, but returns the position of the line in the output pane too,
+/// e.g. to scroll to it.
+pub fn block_search_hit<S, P>(process: P, submsg: S) -> Result<SearchHit>
+where
+    S: ToString,
+    P: ToString,
+{
+    TERMINAL.block_search_hit(process, submsg)
+}
+
+/// Like `block_search_message`, but matching a regex pattern instead of a substring.
+/// Returns an error if the pattern is invalid.
+pub fn block_search_regex<P>(process: P, pattern: &str) -> Result<String>
+where
+    P: ToString,
+{
+    TERMINAL.block_search_regex(process, pattern, false)
+}
+
+/// Like `block_search_regex`, but returns the first capture group of the match instead of the
+/// whole line.
+pub fn block_search_regex_group<P>(process: P, pattern: &str) -> Result<String>
+where
+    P: ToString,
+{
+    TERMINAL.block_search_regex(process, pattern, true)
+}
+
+/// Like `block_search_message`, but returns `None` if no message is found before the timeout.
+pub fn block_search_message_timeout<S, P>(
+    process: P,
+    submsg: S,
+    timeout: Duration,
+) -> Result<Option<String>>
+where
+    S: ToString,
+    P: ToString,
+{
+    TERMINAL.block_search_message_timeout(process, submsg, timeout)
+}
+
+/// Search for a substring in the output already captured from a process, without blocking.
+pub fn try_search_message<S, P>(process: P, submsg: S) -> Result<Option<String>>
+where
+    S: ToString,
+    P: ToString,
+{
+    TERMINAL.try_search_message(process, submsg)
+}
+
+/// Like `try_search_message`, but returns the position of the line in the output pane too.
+pub fn try_search_hit<S, P>(process: P, submsg: S) -> Result<Option<SearchHit>>
+where
+    S: ToString,
+    P: ToString,
+{
+    TERMINAL.try_search_hit(process, submsg)
+}
+
+/// Block the current thread until a process has captured at least `lines` lines in its first
+/// pane. Lines dropped by `max_lines` or cleared still count.
+/// Returns an error if its output ends before, or once `timeout` expires.
+pub fn wait_for_lines<P>(process: P, lines: usize, timeout: Option<Duration>) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.wait_for_lines(process, lines, timeout)
+}
+
+/// Block the current thread until a process has captured its first line, as `wait_for_lines`.
+pub fn wait_for_output<P>(process: P, timeout: Option<Duration>) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.wait_for_lines(process, 1, timeout)
+}
+
+/// Set a fixed header line, shown above the scrolling content of every pane of a process.
+/// An empty line removes the header.
+#[cfg(feature = "tui")]
+pub fn set_pane_header<P>(process: P, header: Line<'static>) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.set_pane_header(process, header)
+}
+
+/// Set a fixed footer line, shown below the scrolling content of every pane of a process.
+/// An empty line removes the footer.
+#[cfg(feature = "tui")]
+pub fn set_pane_footer<P>(process: P, footer: Line<'static>) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.set_pane_footer(process, footer)
+}
+
+/// Spawn `command` and feed every line captured from a process to its stdin as it arrives.
+///
+/// Forwarding stops, with a note in the Main section, once the command stops accepting input.
+/// The stdout and stderr of the command are inherited unless configured otherwise, so they
+/// should be redirected to avoid drawing over the terminal.
+pub fn pipe_to<P>(process: P, command: Command) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.pipe_to(process, command)
+}
+
+/// Lines per second captured from a process (stdout and stderr), averaged over the last
+/// five seconds or since the last `reset_counters` if more recent.
+pub fn output_rate<P>(process: P) -> Result<f64>
+where
+    P: ToString,
+{
+    TERMINAL.output_rate(process)
+}
+
+/// Reset the output counters of a process.
+pub fn reset_counters<P>(process: P) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.reset_counters(process)
+}
+
+/// Empty the panes of a process and reset their scroll.
+pub fn clear_process<P>(process: P) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.clear_process(process)
+}
+
+/// Empty the Main section and reset its scroll.
+pub fn clear_main() {
+    TERMINAL.clear_main();
+}
+
+/// Show `text` in the status bar, enabled with `TerminalSettings::status_bar`, replacing the
+/// previous message, e.g. to notify that a step completed.
+pub fn set_status<S>(text: S)
+where
+    S: ToString,
+{
+    TERMINAL.set_status(text);
+}
+
+/// Remove the message shown in the status bar.
+pub fn clear_status() {
+    TERMINAL.clear_status();
+}
+
+/// Snapshot of the lines currently held in the output pane of a process.
+/// With `MessageSettings::Combined`, it contains the error lines too.
+pub fn process_output<P>(process: P) -> Result<Vec<String>>
+where
+    P: ToString,
+{
+    TERMINAL.process_output(process)
+}
+
+/// Snapshot of the lines currently held in the error pane of a process.
+pub fn process_errors<P>(process: P) -> Result<Vec<String>>
+where
+    P: ToString,
+{
+    TERMINAL.process_errors(process)
+}
+
+/// Snapshot of the lines currently held in the Main section.
+pub fn main_messages() -> Vec<String> {
+    TERMINAL.main_messages()
+}
+
+/// Snapshot of the lines of every process in arrival order, as shown by the merged timeline.
+pub fn merged_messages() -> Vec<String> {
+    TERMINAL.merged_messages()
+}
+
+/// Show the merged timeline in the Main section instead of its messages, as with `m`, or the
+/// Main section again with `false`.
+pub fn set_merged_view(merged: bool) {
+    TERMINAL.set_merged_view(merged)
+}
+
+/// Show only the messages of `source` in the Main section, as cycled with `s`, or all of them
+/// again with `None`. The notes about a process have its name as source.
+pub fn set_source_filter(source: Option<&str>) {
+    TERMINAL.set_source_filter(source)
+}
+
+/// Show the first pane of a process in full screen, as with its focus key, or leave the full
+/// screen with `None`.
+pub fn set_focus(process: Option<&str>) -> Result<()> {
+    TERMINAL.set_focus(process)
+}
+
+/// Name of the process whose pane is in full screen, `None` if it's the Main section or nothing
+/// is in full screen.
+pub fn current_focus() -> Option<String> {
+    TERMINAL.current_focus()
+}
+
+/// Scroll the output pane of a process so the line at `index`, e.g. of a `SearchHit`, is at
+/// the bottom of the view. Scrolling to the last line follows the new lines again.
+pub fn scroll_to<P>(process: P, index: usize) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.scroll_to(process, index)
+}
+
+/// Scroll of the first pane of a process.
+pub fn get_scroll<P>(process: P) -> Result<ScrollStatus>
+where
+    P: ToString,
+{
+    TERMINAL.get_scroll(process)
+}
+
+/// Scroll the first pane of a process by `x` columns, anchoring its bottom at the absolute
+/// position `y`, or following the new lines with `None`. A `y` past the newest line follows them
+/// too.
+pub fn set_scroll<P>(process: P, x: u16, y: Option<usize>) -> Result<()>
+where
+    P: ToString,
+{
+    TERMINAL.set_scroll(process, x, y)
+}
+
+/// Block until a line of a process contains `submsg`, like `block_search_hit`, then show its
+/// pane in full screen, scrolled to the line. The matches of `submsg` are highlighted until `Esc`
+/// is pressed or another search is focused.
+pub fn search_and_focus<S, P>(process: P, submsg: S) -> Result<SearchHit>
+where
+    S: ToString,
+    P: ToString,
+{
+    TERMINAL.search_and_focus(process, submsg)
+}
+
+/// Receive what happens in the terminal: the processes added, removed, exited or restarted,
+/// the focus changes and the lines found by the blocking searches. The events sent before the
+/// call aren't received.
+pub fn subscribe() -> Receiver<TerminalEvent> {
+    TERMINAL.subscribe()
+}
+
+/// Render what the terminal currently shows on a screen of `width` x `height` cells, one line
+/// per row, without drawing on the real terminal. Useful to assert on the layout in tests.
+#[cfg(feature = "tui")]
+pub fn render_to_string(width: u16, height: u16) -> String {
+    TERMINAL.render_to_string(width, height)
+}
+
+/// Close the terminal: stop drawing, restore the screen, terminate the children of the shown
+/// processes and run the exit callback.
+/// The process is only exited with `TerminalSettings::exit_on_close`.
+/// Returns once the screen is restored and the threads of the terminal stopped, waiting at most
+/// a second for the ones capturing an output still open, e.g. of a child kept alive.
 pub fn end_terminal() {
     TERMINAL.kill();
 }
 
+/// Whether the terminal was initialized, explicitly with `init_terminal` or implicitly by any
+/// other function. Checking it doesn't initialize the terminal.
+pub fn is_initialized() -> bool {
+    Terminal::is_initialized()
+}
+
+/// Whether the terminal is drawing, i.e. its draw thread spawned and didn't stop yet.
+/// Always false in headless mode, where nothing is drawn.
+pub fn is_running() -> bool {
+    Terminal::is_running()
+}
+
+/// Block until the terminal is closed, with Ctrl+C or `end_terminal`, and its exit callback ran.
+pub fn wait_terminal_closed() {
+    TERMINAL.wait_closed();
+}
+
+/// Set a callback invoked with the process name, the stream and the content of every line
+/// captured from any process.
+///
+/// The callback runs on the threads reading the process outputs, so it must be fast to avoid
+/// delaying the capture.
+pub fn set_line_tap<F>(closure: F)
+where
+    F: Fn(&str, Stream, &str) + Send + Sync + 'static,
+{
+    TERMINAL.set_line_tap(closure);
+}
+
+/// Run `handler` when the key `code` is pressed along with `modifiers`.
+/// It runs on the thread reading the keyboard, so it must be fast to keep the terminal responsive.
+/// Fails in headless mode, where no key is read, so the handler would never run.
+#[cfg(feature = "tui")]
+pub fn add_key_action<F>(code: KeyCode, modifiers: KeyModifiers, handler: F) -> Result<()>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    TERMINAL.add_key_action(code, modifiers, handler)
+}
+
 pub fn with_exit_callback<F: Fn() + Send + Sync + 'static>(closure: F) {
     TERMINAL.with_exit_callback(closure);
 }
@@ -0,0 +1,161 @@
+use {
+    crate::messages::Style,
+    serde_json::{Map, Value},
+};
+
+/// Rewrite a line holding a JSON object into `key=value` pairs, the keys of nested objects
+/// joined with `.`, along with the styles of the keys. `None` if the line isn't an object.
+pub(crate) fn key_values(
+    line: &str,
+    key_style: Option<Style>,
+) -> Option<(String, Vec<(usize, Style)>)> {
+    // Members are kept in the order they're written.
+    let Value::Object(members) = serde_json::from_str::<Value>(line).ok()? else {
+        return None;
+    };
+
+    let mut text = String::new();
+    let mut styles = vec![];
+
+    flatten(&members, "", &mut text, &mut styles, key_style);
+
+    Some((text, styles))
+}
+
+fn flatten(
+    members: &Map<String, Value>,
+    prefix: &str,
+    text: &mut String,
+    styles: &mut Vec<(usize, Style)>,
+    key_style: Option<Style>,
+) {
+    for (key, value) in members {
+        let key = format!("{prefix}{key}");
+
+        if let Value::Object(members) = value {
+            flatten(members, &format!("{key}."), text, styles, key_style);
+            continue;
+        }
+
+        if !text.is_empty() {
+            text.push(' ');
+        }
+
+        if let Some(key_style) = key_style {
+            styles.push((text.len(), key_style));
+            styles.push((text.len() + key.len(), Style::default()));
+        }
+
+        text.push_str(&key);
+        text.push('=');
+
+        match value {
+            // Bare strings read better, unless they'd be mistaken for several pairs.
+            Value::String(string)
+                if !string.is_empty()
+                    && !string.contains(|c: char| c.is_whitespace() || c == '=' || c == '"') =>
+            {
+                text.push_str(string)
+            }
+            // Written as compact JSON.
+            value => text.push_str(&value.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &str) -> Option<String> {
+        key_values(line, None).map(|(text, _)| text)
+    }
+
+    #[test]
+    fn nested_objects_are_flattened() {
+        assert_eq!(
+            text(r#"{"level":"info","http":{"method":"GET","status":200},"ok":true}"#).unwrap(),
+            "level=info http.method=GET http.status=200 ok=true"
+        );
+    }
+
+    #[test]
+    fn arrays_and_spaced_strings_stay_json() {
+        assert_eq!(
+            text(r#"{ "ids" : [1, -2.5e3, null], "msg": "two words", "empty": "" }"#).unwrap(),
+            r#"ids=[1,-2500.0,null] msg="two words" empty="""#
+        );
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(
+            text(r#"{"path":"a\/b","tab":"a\tb","quote":"\"x\""}"#).unwrap(),
+            r#"path=a/b tab="a\tb" quote="\"x\"""#
+        );
+        assert_eq!(text(r#"{"e":"\u00e9"}"#).unwrap(), "e=é");
+    }
+
+    #[test]
+    fn surrogate_pairs_are_combined() {
+        assert_eq!(text(r#"{"emoji":"\ud83d\ude00"}"#).unwrap(), "emoji=😀");
+
+        // A high surrogate followed by anything but a low one, or a lone low one.
+        assert_eq!(text(r#"{"bad":"\ud83dA"}"#), None);
+        assert_eq!(text(r#"{"bad":"\ud83d\u0041"}"#), None);
+        assert_eq!(text(r#"{"bad":"\ud83d\ue000"}"#), None);
+        assert_eq!(text(r#"{"lone":"\ude00"}"#), None);
+    }
+
+    #[test]
+    fn invalid_json_is_left_alone() {
+        for line in [
+            r#"{"a":1"#,
+            r#"{"a":01}"#,
+            r#"{"a":1.}"#,
+            r#"{"a":.5}"#,
+            r#"{"a":1e}"#,
+            r#"{"a":+1}"#,
+            r#"{"a":inf}"#,
+            r#"{"a":NaN}"#,
+            r#"{"a":tru}"#,
+            r#"{"a":1} trailing"#,
+            r#"{a:1}"#,
+            "{\"a\":\"line\nbreak\"}",
+        ] {
+            assert_eq!(text(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn deeply_nested_lines_are_left_alone() {
+        let line = format!("{}1{}", r#"{"a":"#.repeat(100_000), "}".repeat(100_000));
+
+        assert_eq!(text(&line), None);
+    }
+
+    #[test]
+    fn only_objects_are_rewritten() {
+        for line in [r#"[1,2]"#, r#""text""#, "42", "null", "plain text", ""] {
+            assert_eq!(text(line), None, "{line}");
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn keys_are_styled() {
+        let style = Style::default().add_modifier(ratatui::style::Modifier::BOLD);
+        let (text, styles) = key_values(r#"{"a":1,"bc":2}"#, Some(style)).unwrap();
+
+        assert_eq!(text, "a=1 bc=2");
+        assert_eq!(
+            styles,
+            [
+                (0, style),
+                (1, Style::default()),
+                (4, style),
+                (6, Style::default())
+            ]
+        );
+    }
+}
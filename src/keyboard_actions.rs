@@ -1,74 +1,372 @@
 use {
-    crate::{shared::Shared, ExitCallback, SharedMessages},
-    anyhow::{anyhow, Result},
-    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    crate::{
+        shared::Shared,
+        status::{BaseStatus, FilterStatus, HelpLines, PaneAreas, ScrollStatus},
+        terminal::{
+            clear_focused_pane, clear_highlights, copy_focused_pane, cycle_group, cycle_source,
+            focused_scroll, jump_to_error, move_scroll, page_height, stop_scrolling, toggle_follow,
+            toggle_wrap, Control, KeyHandler, SharedProcesses,
+        },
+        KeyAction, SharedMessages, TerminalSettings,
+    },
+    crossterm::event::{
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
 };
 
 pub struct KeyBoardActions {
     actions: Vec<Action>,
     focus: Shared<Option<usize>>,
+    /// Amount of process panes that can be focused.
+    panes: Shared<usize>,
+    filter: Shared<Option<FilterStatus>>,
+    areas: Shared<PaneAreas>,
+    /// Panes scrolled with the mouse wheel.
+    pane: ActionPane,
+    help: Shared<Option<HelpLines>>,
 }
 
 impl KeyBoardActions {
-    pub fn new(main_messages: SharedMessages) -> (Self, BaseStatus, Shared<ExitCallback>) {
+    pub fn new(
+        main_messages: SharedMessages,
+        merged_messages: SharedMessages,
+        control: Control,
+        settings: &TerminalSettings,
+    ) -> (Self, BaseStatus) {
         let base_status: BaseStatus = Default::default();
-        let exit_callback: Shared<ExitCallback> = Default::default();
+        let panes: Shared<usize> = Default::default();
 
-        let main_action_scroll = ActionScroll {
-            status: base_status.main_scroll.clone(),
-            messages: main_messages.clone(),
+        let focused_pane = ActionPane {
+            focus: base_status.focus.clone(),
+            main_messages: main_messages.clone(),
+            main_scroll: base_status.main_scroll.clone(),
+            merged: base_status.merged.clone(),
+            merged_messages,
+            merged_scroll: base_status.merged_scroll.clone(),
+            processes: control.processes.clone(),
         };
 
-        let actions = vec![
-            Action {
-                event: KeyCode::Char('c').into_event(KeyModifiers::CONTROL),
-                data: ActionType::Close(exit_callback.clone()),
-            },
-            Action {
-                event: KeyCode::Up.into_event_no_modifier(),
-                data: ActionType::ScrollUp(main_action_scroll.clone()),
-            },
-            Action {
-                event: KeyCode::Down.into_event_no_modifier(),
-                data: ActionType::ScrollDown(main_action_scroll.clone()),
-            },
-            Action {
-                event: KeyCode::Left.into_event_no_modifier(),
-                data: ActionType::ScrollLeft(main_action_scroll.clone()),
-            },
-            Action {
-                event: KeyCode::Right.into_event_no_modifier(),
-                data: ActionType::ScrollRight(main_action_scroll.clone()),
-            },
-            Action {
-                event: KeyCode::Char('0').into_event_no_modifier(),
-                data: ActionType::Focus((0, base_status.focus.clone())),
-            },
-            Action {
-                event: KeyCode::Esc.into_event_no_modifier(),
-                data: ActionType::RemoveFocus(base_status.focus.clone()),
-            },
+        let mut actions = vec![
+            Action::new(
+                settings.quit_key.into_event(settings.quit_modifiers),
+                ActionType::Close(control),
+            ),
+            Action::new(
+                KeyCode::Up.into_event_no_modifier(),
+                ActionType::ScrollFocused((focused_pane.clone(), ScrollDirection::Up)),
+            ),
+            Action::new(
+                KeyCode::Down.into_event_no_modifier(),
+                ActionType::ScrollFocused((focused_pane.clone(), ScrollDirection::Down)),
+            ),
+            Action::new(
+                KeyCode::Left.into_event_no_modifier(),
+                ActionType::ScrollFocused((focused_pane.clone(), ScrollDirection::Left)),
+            ),
+            Action::new(
+                KeyCode::Right.into_event_no_modifier(),
+                ActionType::ScrollFocused((focused_pane.clone(), ScrollDirection::Right)),
+            ),
+            Action::new(
+                KeyCode::Char('0').into_event_no_modifier(),
+                ActionType::Focus((0, base_status.focus.clone())),
+            ),
+            Action::new(
+                KeyCode::Esc.into_event_no_modifier(),
+                ActionType::RemoveFocus((
+                    base_status.focus.clone(),
+                    base_status.filter.clone(),
+                    focused_pane.processes.clone(),
+                )),
+            ),
         ];
 
+        let bindings = [
+            (
+                KeyAction::Filter,
+                KeyCode::Char('/').into_event_no_modifier(),
+                ActionType::EnterFilter((base_status.focus.clone(), base_status.filter.clone())),
+            ),
+            (
+                KeyAction::NextPane,
+                KeyCode::Tab.into_event_no_modifier(),
+                ActionType::NextFocus((base_status.focus.clone(), panes.clone())),
+            ),
+            (
+                KeyAction::PreviousPane,
+                KeyCode::BackTab.into_event(KeyModifiers::SHIFT),
+                ActionType::PreviousFocus((base_status.focus.clone(), panes.clone())),
+            ),
+            (
+                KeyAction::NextGroup,
+                KeyCode::Char(']').into_event_no_modifier(),
+                ActionType::NextGroup((base_status.group.clone(), focused_pane.processes.clone())),
+            ),
+            (
+                KeyAction::PreviousGroup,
+                KeyCode::Char('[').into_event_no_modifier(),
+                ActionType::PreviousGroup((
+                    base_status.group.clone(),
+                    focused_pane.processes.clone(),
+                )),
+            ),
+            (
+                KeyAction::Merged,
+                KeyCode::Char('m').into_event_no_modifier(),
+                ActionType::ToggleMerged(base_status.merged.clone()),
+            ),
+            (
+                KeyAction::Source,
+                KeyCode::Char('s').into_event_no_modifier(),
+                ActionType::CycleSource((base_status.source.clone(), focused_pane.clone())),
+            ),
+            (
+                KeyAction::Copy,
+                KeyCode::Char('y').into_event_no_modifier(),
+                ActionType::CopyToClipboard(focused_pane.clone()),
+            ),
+            (
+                KeyAction::Follow,
+                KeyCode::Char('f').into_event_no_modifier(),
+                ActionType::ToggleFollow(focused_pane.clone()),
+            ),
+            (
+                KeyAction::Wrap,
+                KeyCode::Char('w').into_event_no_modifier(),
+                ActionType::ToggleWrap(focused_pane.clone()),
+            ),
+            (
+                KeyAction::Clear,
+                KeyCode::Char('l').into_event(KeyModifiers::CONTROL),
+                ActionType::ClearPane(focused_pane.clone()),
+            ),
+            (
+                KeyAction::Top,
+                KeyCode::Home.into_event_no_modifier(),
+                ActionType::ScrollTop(focused_pane.clone()),
+            ),
+            (
+                KeyAction::Bottom,
+                KeyCode::End.into_event_no_modifier(),
+                ActionType::ScrollBottom(focused_pane.clone()),
+            ),
+            (
+                KeyAction::BottomAll,
+                KeyCode::End.into_event(KeyModifiers::SHIFT),
+                ActionType::ScrollBottomAll(focused_pane.clone()),
+            ),
+            (
+                KeyAction::PageUp,
+                KeyCode::PageUp.into_event_no_modifier(),
+                ActionType::ScrollPageUp(focused_pane.clone()),
+            ),
+            (
+                KeyAction::PageDown,
+                KeyCode::PageDown.into_event_no_modifier(),
+                ActionType::ScrollPageDown(focused_pane.clone()),
+            ),
+            (
+                KeyAction::NextError,
+                KeyCode::Char('n').into_event_no_modifier(),
+                ActionType::NextError(focused_pane.clone()),
+            ),
+            (
+                KeyAction::PreviousError,
+                KeyCode::Char('N').into_event(KeyModifiers::SHIFT),
+                ActionType::PreviousError(focused_pane.clone()),
+            ),
+        ];
+
+        for (action, default, data) in bindings {
+            let mut events = settings
+                .key_bindings
+                .iter()
+                .filter(|(bound, ..)| *bound == action)
+                .map(|(_, key, modifiers)| key.into_event(*modifiers))
+                .collect::<Vec<_>>();
+
+            if events.is_empty() {
+                events.push(default);
+            }
+
+            actions.extend(
+                events
+                    .into_iter()
+                    .map(|event| Action::new(event, data.clone())),
+            );
+        }
+
         (
             Self {
                 actions,
                 focus: base_status.focus.clone(),
+                panes,
+                filter: base_status.filter.clone(),
+                areas: base_status.areas.clone(),
+                pane: focused_pane,
+                help: base_status.help.clone(),
             },
             base_status,
-            exit_callback,
         )
     }
 
-    pub fn apply_event(&self, event: Event) {
+    /// Apply the actions bound to `event`, returning the custom handlers to run.
+    pub fn apply_event(&self, event: Event) -> Vec<KeyHandler> {
+        if self.edit_filter(&event) || self.toggle_help(&event) {
+            return vec![];
+        }
+
+        if let Event::Mouse(mouse) = event {
+            self.apply_mouse(mouse);
+            return vec![];
+        }
+
         let events = self
             .actions
             .iter()
             .filter(|action| action.event == event)
             .collect::<Vec<_>>();
 
+        let mut handlers = vec![];
+
         for action in events {
-            action.data.apply();
+            match &action.data {
+                ActionType::Custom(handler) => handlers.push(handler.clone()),
+                data => data.apply(),
+            }
+        }
+
+        handlers
+    }
+
+    /// Route the typed characters to the filter while it's being edited, returning whether the
+    /// event was consumed.
+    fn edit_filter(&self, event: &Event) -> bool {
+        let Event::Key(key) = event else {
+            return false;
+        };
+
+        self.filter.write_with(|mut filter| {
+            let Some(status) = filter.as_mut().filter(|status| status.editing) else {
+                return false;
+            };
+
+            match key.code {
+                KeyCode::Char(char) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => {
+                    status.text.push(char);
+                }
+                KeyCode::Backspace => {
+                    status.text.pop();
+                }
+                KeyCode::Enter => status.editing = false,
+                KeyCode::Esc => *filter = None,
+                _ => return false,
+            }
+
+            true
+        })
+    }
+
+    /// Open the help popup with `?`, or close it with `?` or Esc, returning whether the event
+    /// was consumed.
+    fn toggle_help(&self, event: &Event) -> bool {
+        let Event::Key(key) = event else {
+            return false;
+        };
+
+        let is_help_key =
+            key.code == KeyCode::Char('?') && (key.modifiers - KeyModifiers::SHIFT).is_empty();
+
+        self.help.write_with(|mut help| {
+            if help.is_some() && (is_help_key || key.code == KeyCode::Esc) {
+                *help = None;
+            } else if help.is_none() && is_help_key {
+                *help = Some(self.help_lines());
+            } else {
+                return false;
+            }
+
+            true
+        })
+    }
+
+    /// Every binding along with what it does, the digits focusing the panes grouped in a single
+    /// line.
+    fn help_lines(&self) -> HelpLines {
+        let mut lines = vec![("?".to_string(), "Show or hide this help".to_string())];
+        let mut digits = vec![];
+
+        for action in &self.actions {
+            if matches!(action.data, ActionType::Focus((index, _)) if index != 0)
+                && action.owner.is_none()
+            {
+                digits.push(describe_event(&action.event));
+                continue;
+            }
+
+            let label = match &action.owner {
+                Some(owner) => format!("{} ({owner})", action.data.label()),
+                None => action.data.label(),
+            };
+
+            lines.push((describe_event(&action.event), label));
+        }
+
+        // Contiguous unless processes have their own focus key.
+        let keys = match digits.as_slice() {
+            [] => None,
+            [digit] => Some(digit.clone()),
+            [first, .., last] if digits.len() == last.parse().unwrap_or(0) => {
+                Some(format!("{first}-{last}"))
+            }
+            _ => Some(digits.join(",")),
+        };
+
+        if let Some(keys) = keys {
+            lines.push((
+                keys,
+                "Show the pane with that number in full screen".to_string(),
+            ));
+        }
+
+        lines
+    }
+
+    /// Scroll the pane under the cursor with the wheel, or put it in full screen with a click.
+    fn apply_mouse(&self, mouse: MouseEvent) {
+        let Some(focus) = self.areas.read_with(|areas| {
+            areas
+                .iter()
+                .find(|(area, _)| area.contains((mouse.column, mouse.row).into()))
+                .map(|(_, focus)| *focus)
+        }) else {
+            return;
+        };
+
+        let direction = match mouse.kind {
+            MouseEventKind::ScrollUp => ScrollDirection::Up,
+            MouseEventKind::ScrollDown => ScrollDirection::Down,
+            MouseEventKind::Down(MouseButton::Left) => {
+                // In full screen, the only pane is already focused.
+                self.focus.write_with(|mut current| {
+                    if current.is_none() {
+                        *current = Some(focus);
+                    }
+                });
+
+                return;
+            }
+            _ => return,
+        };
+
+        if let Some((status, messages)) = focused_scroll(&self.pane, focus) {
+            let scroll = ActionScroll { status, messages };
+
+            match direction {
+                ScrollDirection::Up => ActionType::ScrollUp(scroll),
+                _ => ActionType::ScrollDown(scroll),
+            }
+            .apply();
         }
     }
 
@@ -76,83 +374,235 @@ impl KeyBoardActions {
         self.actions.push(action);
     }
 
-    pub fn push_focus(&mut self, indexes: &[usize]) -> Result<()> {
-        for index in indexes {
-            let char = to_char(*index)?;
+    /// Bind the full screen focus of each pane to its key in `keys`, along with the process
+    /// owning it, or to its digit (up to 9) when it has none.
+    /// Every pane, past the ninth too, can be reached cycling with Tab/Shift+Tab.
+    pub fn set_focus_keys(&mut self, keys: Vec<Option<(KeyCode, String)>>) {
+        self.actions
+            .retain(|action| !matches!(action.data, ActionType::Focus((index, _)) if index != 0));
 
-            self.push(Action::new(
-                KeyCode::Char(char).into_event_no_modifier(),
-                ActionType::Focus((*index, self.focus.clone())),
-            ));
+        let count = keys.len();
+
+        for (index, key) in (1..).zip(keys) {
+            let action = match key {
+                Some((code, owner)) => Action::new(
+                    code.into_event_no_modifier(),
+                    ActionType::Focus((index, self.focus.clone())),
+                )
+                .owned_by(&owner),
+                None => {
+                    let Some(char) = to_char(index) else {
+                        continue;
+                    };
+
+                    Action::new(
+                        KeyCode::Char(char).into_event_no_modifier(),
+                        ActionType::Focus((index, self.focus.clone())),
+                    )
+                }
+            };
+
+            self.push(action);
         }
 
-        Ok(())
+        self.panes.write_with(|mut panes| *panes = count);
+    }
+
+    pub fn remove_owned(&mut self, owner: &str) {
+        self.actions
+            .retain(|action| action.owner.as_deref() != Some(owner));
     }
 }
 
 pub struct Action {
     pub event: Event,
     pub data: ActionType,
+    /// Process that registered the action, if any.
+    pub owner: Option<String>,
 }
 
 impl Action {
     pub fn new(event: Event, data: ActionType) -> Self {
-        Self { event, data }
+        Self {
+            event,
+            data,
+            owner: None,
+        }
+    }
+
+    pub fn owned_by(self, owner: &str) -> Self {
+        Self {
+            owner: Some(owner.to_string()),
+            ..self
+        }
     }
 }
 
+#[derive(Clone)]
 pub enum ActionType {
-    Close(Shared<ExitCallback>),
+    Close(Control),
     ScrollUp(ActionScroll),
     ScrollDown(ActionScroll),
     ScrollLeft(ActionScroll),
     ScrollRight(ActionScroll),
     StopScrolling(Shared<ScrollStatus>),
+    /// Scroll the pane in full screen, or the Main section, by one step.
+    ScrollFocused((ActionPane, ScrollDirection)),
     Focus((usize, Shared<Option<usize>>)),
-    RemoveFocus(Shared<Option<usize>>),
+    /// Exit full screen, dropping the filter and the highlight of the last search.
+    RemoveFocus(
+        (
+            Shared<Option<usize>>,
+            Shared<Option<FilterStatus>>,
+            SharedProcesses,
+        ),
+    ),
+    /// Start typing a filter for the pane in full screen.
+    EnterFilter((Shared<Option<usize>>, Shared<Option<FilterStatus>>)),
+    /// Cycle the full screen focus forward through the Main section and the process panes.
+    NextFocus((Shared<Option<usize>>, Shared<usize>)),
+    /// Cycle the full screen focus backward.
+    PreviousFocus((Shared<Option<usize>>, Shared<usize>)),
+    /// Show the tab of the next group of processes.
+    NextGroup((Shared<Option<String>>, SharedProcesses)),
+    /// Show the tab of the previous group of processes.
+    PreviousGroup((Shared<Option<String>>, SharedProcesses)),
+    /// Switch the Main section between its own messages and the merged timeline of the
+    /// processes.
+    ToggleMerged(Shared<bool>),
+    /// Show the messages of the next source alone in the Main section, then all of them again.
+    CycleSource((Shared<Option<String>>, ActionPane)),
+    CopyToClipboard(ActionPane),
+    /// Stop or resume following the tail of the pane in full screen, or of the Main section.
+    ToggleFollow(ActionPane),
+    /// Switch between wrapping and clipping the long lines of the pane in full screen, or of the
+    /// Main section.
+    ToggleWrap(ActionPane),
+    /// Empty the pane in full screen, or the Main section.
+    ClearPane(ActionPane),
+    /// Scroll the pane in full screen, or the Main section, to its oldest message.
+    ScrollTop(ActionPane),
+    /// Follow again the tail of the pane in full screen, or of the Main section, scrolled back to
+    /// the start of the lines.
+    ScrollBottom(ActionPane),
+    /// Follow again the tail of the Main section and of every pane.
+    ScrollBottomAll(ActionPane),
+    ScrollPageUp(ActionPane),
+    ScrollPageDown(ActionPane),
+    /// Anchor the pane in full screen, or the Main section, to the next error line.
+    NextError(ActionPane),
+    /// Anchor the pane in full screen, or the Main section, to the previous error line.
+    PreviousError(ActionPane),
+    /// Handler registered with `add_key_action`, run by the input thread.
+    Custom(KeyHandler),
 }
 
 impl ActionType {
-    pub fn apply(&self) {
+    /// What the action does, shown by the help popup.
+    pub fn label(&self) -> String {
         match self {
-            ActionType::Close(exit_callback) => {
-                ratatui::restore();
-
-                if let Some(callback) = exit_callback.read_access().as_ref() {
-                    callback();
-                }
-
-                std::process::exit(0);
+            ActionType::Close(_) => "Close the terminal",
+            ActionType::ScrollUp(_) => "Scroll up",
+            ActionType::ScrollDown(_) => "Scroll down",
+            ActionType::ScrollLeft(_) => "Scroll left",
+            ActionType::ScrollRight(_) => "Scroll right",
+            ActionType::StopScrolling(_) => "Stop scrolling",
+            ActionType::ScrollFocused((_, ScrollDirection::Up)) => "Scroll up",
+            ActionType::ScrollFocused((_, ScrollDirection::Down)) => "Scroll down",
+            ActionType::ScrollFocused((_, ScrollDirection::Left)) => "Scroll left",
+            ActionType::ScrollFocused((_, ScrollDirection::Right)) => "Scroll right",
+            ActionType::Focus((0, _)) => "Show the Main section in full screen",
+            ActionType::Focus(_) => "Show the pane in full screen",
+            ActionType::RemoveFocus(_) => "Exit full screen",
+            ActionType::EnterFilter(_) => "Filter the lines of the pane in full screen",
+            ActionType::NextFocus(_) => "Show the next pane in full screen",
+            ActionType::PreviousFocus(_) => "Show the previous pane in full screen",
+            ActionType::NextGroup(_) => "Show the next group of processes",
+            ActionType::PreviousGroup(_) => "Show the previous group of processes",
+            ActionType::ToggleMerged(_) => "Show the merged timeline or the Main section",
+            ActionType::CycleSource(_) => {
+                "Show the messages of the next source in the Main section"
             }
+            ActionType::CopyToClipboard(_) => "Copy the pane in full screen",
+            ActionType::ToggleFollow(_) => "Pause or resume following the new lines",
+            ActionType::ToggleWrap(_) => "Wrap or clip the long lines",
+            ActionType::ClearPane(_) => "Clear the pane",
+            ActionType::ScrollTop(_) => "Scroll to the oldest line",
+            ActionType::ScrollBottom(_) => "Scroll to the newest line",
+            ActionType::ScrollBottomAll(_) => "Scroll every pane to its newest line",
+            ActionType::ScrollPageUp(_) => "Scroll up by a page",
+            ActionType::ScrollPageDown(_) => "Scroll down by a page",
+            ActionType::NextError(_) => "Jump to the next error line",
+            ActionType::PreviousError(_) => "Jump to the previous error line",
+            ActionType::Custom(_) => "Custom action",
+        }
+        .to_string()
+    }
+
+    pub fn apply(&self) {
+        match self {
+            ActionType::Close(control) => control.close(),
             ActionType::ScrollUp(shared) => {
+                let (first, end) = shared
+                    .messages
+                    .read_with(|messages| (messages.end() - messages.len(), messages.end()));
+
                 shared.status.write_with(|mut status| {
                     if let Some(y) = &mut status.y {
-                        *y = y.saturating_sub(1);
+                        // Stop at the oldest message still in the buffer.
+                        *y = y.saturating_sub(1).max(first + 1);
                     } else {
-                        status.y = Some(shared.messages.read_access().len() as u16);
+                        status.y = Some(end);
                     }
                 });
             }
             ActionType::ScrollDown(shared) => {
+                let end = shared.messages.read_access().end();
+
                 shared.status.write_with(|mut status| {
                     if let Some(y) = &mut status.y {
-                        *y += 1
+                        // Past the tail, the view would follow the new messages again.
+                        *y = (*y + 1).min(end);
                     }
                 });
             }
+            // Wrapped lines are fully visible, so there's nothing to scroll horizontally.
             ActionType::ScrollLeft(shared) => {
                 shared.status.write_with(|mut status| {
-                    status.x = status.x.saturating_sub(1);
+                    if !status.wrap {
+                        status.x = status.x.saturating_sub(1);
+                    }
                 });
             }
             ActionType::ScrollRight(shared) => {
+                let width = shared.messages.read_access().max_width();
+
                 shared.status.write_with(|mut status| {
-                    status.x = status.x + 1;
+                    // Stop once the last character of the longest line is at the left edge.
+                    if !status.wrap && (status.x as usize) + 1 < width {
+                        status.x += 1;
+                    }
                 });
             }
+            ActionType::ScrollFocused((pane, direction)) => {
+                let focus = *pane.focus.read_access();
+
+                if let Some((status, messages)) = focused_scroll(pane, focus.unwrap_or(0)) {
+                    let scroll = ActionScroll { status, messages };
+
+                    match direction {
+                        ScrollDirection::Up => ActionType::ScrollUp(scroll),
+                        ScrollDirection::Down => ActionType::ScrollDown(scroll),
+                        ScrollDirection::Left => ActionType::ScrollLeft(scroll),
+                        ScrollDirection::Right => ActionType::ScrollRight(scroll),
+                    }
+                    .apply();
+                }
+            }
             ActionType::StopScrolling(shared) => {
                 shared.write_with(|mut status| {
                     status.y = None;
+                    status.x = 0;
                 });
             }
             ActionType::Focus((index, shared)) => {
@@ -160,19 +610,117 @@ impl ActionType {
                     *focus = Some(*index);
                 });
             }
-            ActionType::RemoveFocus(shared) => {
+            ActionType::RemoveFocus((shared, filter, processes)) => {
                 shared.write_with(|mut focus| {
                     *focus = None;
                 });
+
+                filter.write_with(|mut filter| {
+                    *filter = None;
+                });
+
+                clear_highlights(processes);
+            }
+            ActionType::EnterFilter((shared, filter)) => {
+                if let Some(focus) = *shared.read_access() {
+                    filter.write_with(|mut filter| {
+                        *filter = Some(FilterStatus {
+                            focus,
+                            text: String::new(),
+                            editing: true,
+                        });
+                    });
+                }
+            }
+            ActionType::NextFocus((shared, panes)) => {
+                let panes = *panes.read_access();
+
+                shared.write_with(|mut focus| {
+                    *focus = Some((focus.unwrap_or(0) + 1) % (panes + 1));
+                });
+            }
+            ActionType::PreviousFocus((shared, panes)) => {
+                let panes = *panes.read_access();
+
+                shared.write_with(|mut focus| {
+                    *focus = Some((focus.unwrap_or(0) + panes) % (panes + 1));
+                });
+            }
+            ActionType::NextGroup((group, processes)) => cycle_group(group, processes, true),
+            ActionType::PreviousGroup((group, processes)) => cycle_group(group, processes, false),
+            ActionType::ToggleMerged(merged) => {
+                merged.write_with(|mut merged| *merged = !*merged);
+            }
+            ActionType::CycleSource((source, pane)) => cycle_source(source, pane),
+            ActionType::CopyToClipboard(pane) => {
+                // Only the pane in full screen is copied.
+                let focus = *pane.focus.read_access();
+
+                if let Some(focus) = focus {
+                    copy_focused_pane(pane, focus);
+                }
+            }
+            ActionType::ToggleFollow(pane) => {
+                let focus = *pane.focus.read_access();
+
+                toggle_follow(pane, focus.unwrap_or(0));
+            }
+            ActionType::ToggleWrap(pane) => {
+                let focus = *pane.focus.read_access();
+
+                toggle_wrap(pane, focus.unwrap_or(0));
+            }
+            ActionType::ClearPane(pane) => {
+                let focus = *pane.focus.read_access();
+
+                clear_focused_pane(pane, focus.unwrap_or(0));
+            }
+            ActionType::Custom(handler) => handler(),
+            ActionType::ScrollTop(pane) => {
+                let focus = *pane.focus.read_access();
+
+                move_scroll(pane, focus.unwrap_or(0), |_, first| first);
+            }
+            ActionType::ScrollBottom(pane) => {
+                let focus = *pane.focus.read_access();
+
+                stop_scrolling(pane, Some(focus.unwrap_or(0)));
+            }
+            ActionType::ScrollBottomAll(pane) => stop_scrolling(pane, None),
+            ActionType::ScrollPageUp(pane) => {
+                let focus = *pane.focus.read_access();
+
+                move_scroll(pane, focus.unwrap_or(0), |y, _| {
+                    y.saturating_sub(page_height())
+                });
+            }
+            ActionType::ScrollPageDown(pane) => {
+                let focus = *pane.focus.read_access();
+
+                move_scroll(pane, focus.unwrap_or(0), |y, _| {
+                    y.saturating_add(page_height())
+                });
+            }
+            ActionType::NextError(pane) => {
+                let focus = *pane.focus.read_access();
+
+                jump_to_error(pane, focus.unwrap_or(0), true);
+            }
+            ActionType::PreviousError(pane) => {
+                let focus = *pane.focus.read_access();
+
+                jump_to_error(pane, focus.unwrap_or(0), false);
             }
         }
     }
 }
 
-#[derive(Default, Clone, PartialEq)]
-pub(crate) struct ScrollStatus {
-    pub x: u16,
-    pub y: Option<u16>,
+#[derive(Clone, Copy)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
 }
 
 #[derive(Clone)]
@@ -181,6 +729,19 @@ pub(crate) struct ActionScroll {
     pub messages: SharedMessages,
 }
 
+/// Resolves the pane targeted by the current focus.
+#[derive(Clone)]
+pub(crate) struct ActionPane {
+    pub focus: Shared<Option<usize>>,
+    pub main_messages: SharedMessages,
+    pub main_scroll: Shared<ScrollStatus>,
+    /// Whether the Main section shows the merged timeline.
+    pub merged: Shared<bool>,
+    pub merged_messages: SharedMessages,
+    pub merged_scroll: Shared<ScrollStatus>,
+    pub processes: SharedProcesses,
+}
+
 pub trait KeyCodeExt: Sized {
     fn into_event(self, modifier: KeyModifiers) -> Event;
 
@@ -195,30 +756,73 @@ impl KeyCodeExt for KeyCode {
     }
 }
 
-pub type DetachBaseStatus = BaseStatus<ScrollStatus, Option<usize>>;
+/// Keys of a binding, e.g. `Ctrl+c`.
+fn describe_event(event: &Event) -> String {
+    let Event::Key(key) = event else {
+        return "?".to_string();
+    };
 
-#[derive(Default, Clone, PartialEq)]
-pub struct BaseStatus<MS = Shared<ScrollStatus>, F = Shared<Option<usize>>> {
-    pub main_scroll: MS,
-    pub focus: F,
-}
+    let mut keys = String::new();
+
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "Ctrl+"),
+        (KeyModifiers::ALT, "Alt+"),
+        (KeyModifiers::SHIFT, "Shift+"),
+    ] {
+        // The case of a character already tells whether Shift is pressed.
+        let implied = modifier == KeyModifiers::SHIFT && matches!(key.code, KeyCode::Char(_));
 
-impl BaseStatus {
-    pub fn detach(&self) -> BaseStatus<ScrollStatus, Option<usize>> {
-        BaseStatus {
-            main_scroll: self.main_scroll.read_access().clone(),
-            focus: self.focus.read_access().clone(),
+        if key.modifiers.contains(modifier) && !implied {
+            keys.push_str(name);
         }
     }
+
+    match key.code {
+        KeyCode::BackTab => keys.push_str("Tab"),
+        code => keys.push_str(&code.to_string()),
+    }
+
+    keys
+}
+
+fn to_char(index: usize) -> Option<char> {
+    char::from_digit(u32::try_from(index).ok()?, 10)
 }
 
-fn to_char(index: usize) -> Result<char> {
-    let str_index = index.to_string();
-    let mut chars = str_index.chars();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_bindings_replace_the_default_keys() {
+        let settings = TerminalSettings {
+            key_bindings: vec![
+                (KeyAction::Copy, KeyCode::Char('c'), KeyModifiers::empty()),
+                (KeyAction::Copy, KeyCode::F(2), KeyModifiers::empty()),
+            ],
+            ..Default::default()
+        };
+
+        let (actions, _) = KeyBoardActions::new(
+            Default::default(),
+            Default::default(),
+            Control::new(Default::default(), false, &settings),
+            &settings,
+        );
+
+        let copy_keys = actions
+            .help_lines()
+            .into_iter()
+            .filter(|(_, label)| label == "Copy the pane in full screen")
+            .map(|(keys, _)| keys)
+            .collect::<Vec<_>>();
+
+        assert_eq!(copy_keys, ["c", "F2"]);
 
-    if let (Some(char), None) = (chars.next(), chars.next()) {
-        Ok(char)
-    } else {
-        return Err(anyhow!("Can't add more then 9 processes."));
+        // The other actions keep their key.
+        assert!(actions.help_lines().contains(&(
+            "f".to_string(),
+            "Pause or resume following the new lines".to_string()
+        )));
     }
 }
@@ -1,8 +1,37 @@
+mod capture;
+#[cfg(feature = "config")]
+mod config;
+mod counters;
+mod events;
 mod functions;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "tui")]
 mod keyboard_actions;
+#[cfg(feature = "log")]
+mod logger;
+mod messages;
+mod redraw;
+#[cfg(feature = "tui")]
+mod render;
+#[cfg(feature = "tui")]
+mod screen;
+#[cfg(feature = "serde")]
+mod serialize;
 mod settings;
 mod shared;
+mod shutdown;
+mod status;
 mod terminal;
 pub mod utils;
 
-pub use {crossterm::event::KeyCode, functions::*, settings::*, terminal::*};
+pub use {events::TerminalEvent, functions::*, settings::*, status::ScrollStatus, terminal::*};
+
+#[cfg(feature = "tui")]
+pub use {
+    crossterm::event::{KeyCode, KeyModifiers},
+    ratatui::{layout::Alignment, style::Color, text::Line},
+};
+
+#[cfg(feature = "log")]
+pub use logger::*;
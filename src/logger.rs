@@ -0,0 +1,137 @@
+use {
+    crate::TERMINAL,
+    anyhow::{anyhow, Result},
+    log::{Level, LevelFilter, Log, Metadata, Record},
+    std::str::FromStr,
+};
+
+/// Install a `log` implementation printing the records in the Main section of the terminal.
+///
+/// `spec` follows the `RUST_LOG` syntax: comma separated `target=level` directives, along with an
+/// optional bare `level` for the other targets (e.g. `warn,my_app=debug`). Records are colored
+/// by level like `tinfo!`/`twarn!`/`terror!`, traces being shown as debug messages.
+pub fn init_logger(spec: &str) -> Result<()> {
+    let logger = TerminalLogger::parse(spec)?;
+
+    let max_level = logger
+        .targets
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(logger.default, Ord::max);
+
+    log::set_logger(Box::leak(Box::new(logger)))
+        .map_err(|_| anyhow!("A logger is already installed."))?;
+
+    log::set_max_level(max_level);
+
+    Ok(())
+}
+
+struct TerminalLogger {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl TerminalLogger {
+    fn parse(spec: &str) -> Result<Self> {
+        let mut logger = TerminalLogger {
+            default: LevelFilter::Error,
+            targets: vec![],
+        };
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let parse_level = |level: &str| {
+                LevelFilter::from_str(level)
+                    .map_err(|_| anyhow!("Invalid log level '{level}' in '{directive}'."))
+            };
+
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    logger
+                        .targets
+                        .push((target.to_string(), parse_level(level)?));
+                }
+                None => match parse_level(directive) {
+                    Ok(level) => logger.default = level,
+                    // A bare target enables all of its levels.
+                    Err(_) => logger
+                        .targets
+                        .push((directive.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+
+        Ok(logger)
+    }
+
+    /// Level of the longest directive matching `target`, or the default one.
+    fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| target == prefix || target.starts_with(&format!("{prefix}::")))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+}
+
+impl Log for TerminalLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = format!(
+            "{:<5} {}: {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        let level = match record.level() {
+            Level::Error => crate::Level::Error,
+            Level::Warn => crate::Level::Warn,
+            Level::Info => crate::Level::Info,
+            Level::Debug | Level::Trace => crate::Level::Debug,
+        };
+
+        TERMINAL.add_level_message_from(level, Some(record.target()), message);
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_sets_the_levels_by_target() {
+        let logger = TerminalLogger::parse("warn, my_app=debug,my_app::db=off,quiet").unwrap();
+
+        assert_eq!(logger.level("other"), LevelFilter::Warn);
+        assert_eq!(logger.level("my_app"), LevelFilter::Debug);
+        assert_eq!(logger.level("my_app::http"), LevelFilter::Debug);
+        assert_eq!(logger.level("my_app::db"), LevelFilter::Off);
+        assert_eq!(logger.level("my_application"), LevelFilter::Warn);
+        assert_eq!(logger.level("quiet"), LevelFilter::Trace);
+
+        assert_eq!(
+            TerminalLogger::parse("").unwrap().level("any"),
+            LevelFilter::Error
+        );
+    }
+
+    #[test]
+    fn invalid_levels_are_rejected() {
+        let error = TerminalLogger::parse("my_app=loud").err().unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid log level 'loud' in 'my_app=loud'."
+        );
+    }
+}
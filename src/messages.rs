@@ -0,0 +1,499 @@
+use {
+    crate::{redraw::Redraw, shared::Shared, Level, Stream, Timestamps},
+    anyhow::{anyhow, Result},
+    std::{
+        collections::VecDeque,
+        fs::{File, OpenOptions},
+        io::{stdout, LineWriter, Write},
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicU64, Ordering},
+        time::{Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+#[cfg(feature = "tui")]
+pub(crate) use ratatui::style::Style;
+
+/// Stands in for the style of the spans of a line without the `tui` feature, nothing being drawn
+/// with it: the lines are left unstyled.
+#[cfg(not(feature = "tui"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Style {}
+
+#[derive(Clone, PartialEq)]
+pub struct Message {
+    pub text: String,
+    pub stream: Stream,
+    pub timestamp: Option<String>,
+    /// Styles parsed from the ANSI codes of the line, each one applied from its byte offset in
+    /// `text` up to the next one.
+    pub styles: Vec<(usize, Style)>,
+    /// How many times in a row the line was captured, when the repeats are collapsed.
+    pub repeated: usize,
+    /// Severity the message was printed with, in the Main section.
+    pub level: Option<Level>,
+    /// Whether the line is an error, jumped to with `n`/`N`: read from stderr, printed as an
+    /// error, or matching `ProcessSettings::error_pattern`.
+    pub error: bool,
+    /// Process or component the message comes from, shown alone with `set_source_filter`.
+    pub source: Option<String>,
+}
+
+impl Message {
+    pub fn new(text: String, stream: Stream) -> Self {
+        Self {
+            text,
+            stream,
+            timestamp: None,
+            styles: vec![],
+            repeated: 1,
+            level: None,
+            error: stream == Stream::Err,
+            source: None,
+        }
+    }
+
+    pub fn with_styles(self, styles: Vec<(usize, Style)>) -> Self {
+        Self { styles, ..self }
+    }
+
+    pub fn with_level(self, level: Level) -> Self {
+        Self {
+            level: Some(level),
+            ..self
+        }
+    }
+
+    pub fn with_source<S: ToString>(self, source: S) -> Self {
+        Self {
+            source: Some(source.to_string()),
+            ..self
+        }
+    }
+}
+
+impl Timestamps {
+    fn stamp(&self, start: Instant) -> Option<String> {
+        match self {
+            Timestamps::None => None,
+            Timestamps::Time => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+
+                let seconds = now.as_secs() % 86_400;
+
+                Some(format!(
+                    "[{:02}:{:02}:{:02}.{:03}] ",
+                    seconds / 3_600,
+                    seconds / 60 % 60,
+                    seconds % 60,
+                    now.subsec_millis()
+                ))
+            }
+            Timestamps::ElapsedSinceStart => {
+                let elapsed = start.elapsed();
+
+                Some(format!(
+                    "[+{}.{:03}s] ",
+                    elapsed.as_secs(),
+                    elapsed.subsec_millis()
+                ))
+            }
+        }
+    }
+}
+
+/// Source of the buffer generations, shared by every buffer so that a generation identifies a
+/// single state of a single buffer.
+static GENERATIONS: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    GENERATIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Buffer of the messages shown in a pane, dropping the oldest ones once `max_lines` is reached.
+/// Messages are timestamped when pushed, which is when they are captured.
+///
+/// Positions returned by `end` are absolute, counting the dropped messages too, so a position
+/// keeps pointing to the same message while the front of the buffer is trimmed.
+///
+/// The generation changes on every write, so the draw thread can tell whether a buffer changed
+/// without comparing its messages.
+#[derive(Clone, PartialEq)]
+pub struct Messages {
+    lines: VecDeque<Message>,
+    max_lines: Option<usize>,
+    dropped: usize,
+    generation: u64,
+    timestamps: Timestamps,
+    start: Instant,
+    log: Option<LogFile>,
+    /// Prefix of the messages printed to stdout as they're pushed, in headless mode.
+    echo: Option<String>,
+    /// Signal of the terminal showing the buffer, requested on every write.
+    redraw: Redraw,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::new(None, Timestamps::None)
+    }
+}
+
+impl Messages {
+    pub fn new(max_lines: Option<usize>, timestamps: Timestamps) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            max_lines,
+            dropped: 0,
+            generation: next_generation(),
+            timestamps,
+            start: Instant::now(),
+            log: None,
+            echo: None,
+            redraw: Redraw::default(),
+        }
+    }
+
+    /// Append every message pushed from now on to `log`.
+    pub fn set_log(&mut self, log: LogFile) {
+        self.log = Some(log);
+    }
+
+    /// Print every message pushed from now on to stdout, after `prefix`.
+    pub fn set_echo(&mut self, prefix: String) {
+        self.echo = Some(prefix);
+    }
+
+    /// Request `redraw` on every write from now on.
+    pub fn set_redraw(&mut self, redraw: Redraw) {
+        self.redraw = redraw;
+    }
+
+    /// Write a captured line to the log and stdout, if enabled.
+    fn write_out(&self, timestamp: Option<&str>, text: &str) {
+        let timestamp = timestamp.unwrap_or_default();
+
+        if let Some(log) = &self.log {
+            log.write(timestamp, text);
+        }
+
+        if let Some(prefix) = &self.echo {
+            // A closed stdout must not stop the capture of the process.
+            let _ = writeln!(stdout().lock(), "{prefix}{timestamp}{text}");
+        }
+    }
+
+    pub fn push(&mut self, mut message: Message) {
+        message.timestamp = self.timestamps.stamp(self.start);
+
+        self.write_out(message.timestamp.as_deref(), &message.text);
+
+        self.lines.push_back(message);
+        self.generation = next_generation();
+        self.redraw.request();
+
+        if let Some(max_lines) = self.max_lines {
+            while self.lines.len() > max_lines {
+                self.lines.pop_front();
+                self.dropped += 1;
+            }
+        }
+    }
+
+    /// Overwrite the last message with `message`, or push it if the last one is from another
+    /// stream.
+    pub fn replace_last(&mut self, mut message: Message) {
+        if self
+            .lines
+            .back()
+            .is_none_or(|last| last.stream != message.stream)
+        {
+            return self.push(message);
+        }
+
+        message.timestamp = self.timestamps.stamp(self.start);
+
+        self.write_out(message.timestamp.as_deref(), &message.text);
+
+        if let Some(last) = self.lines.back_mut() {
+            *last = message;
+        }
+
+        self.generation = next_generation();
+        self.redraw.request();
+    }
+
+    /// Count one more repeat of the last message if it has the same `text` and `stream`, returning
+    /// whether it did. The repeat is still written to the log.
+    pub fn repeat_last(&mut self, text: &str, stream: Stream) -> bool {
+        let Some(last) = self.lines.back_mut() else {
+            return false;
+        };
+
+        if last.text != text || last.stream != stream {
+            return false;
+        }
+
+        last.repeated += 1;
+
+        self.write_out(self.timestamps.stamp(self.start).as_deref(), text);
+
+        self.generation = next_generation();
+        self.redraw.request();
+
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.dropped += self.lines.len();
+        self.lines.clear();
+        self.generation = next_generation();
+
+        self.redraw.request();
+    }
+
+    /// Restart the elapsed time of the timestamps, when the process is spawned again.
+    pub fn restart_clock(&mut self) {
+        self.start = Instant::now();
+    }
+
+    /// Copy of the buffer keeping only the messages containing `text`.
+    #[cfg(feature = "tui")]
+    pub fn filtered(&self, text: &str) -> Messages {
+        let mut messages = self.clone();
+
+        messages.lines.retain(|message| message.text.contains(text));
+
+        messages
+    }
+
+    /// Copy of the buffer keeping only the messages of `source`.
+    #[cfg(feature = "tui")]
+    pub fn of_source(&self, source: &str) -> Messages {
+        let mut messages = self.clone();
+
+        messages
+            .lines
+            .retain(|message| message.source.as_deref() == Some(source));
+
+        messages
+    }
+
+    /// Sources of the messages, in the order of their first message.
+    #[cfg(feature = "tui")]
+    pub fn sources(&self) -> Vec<String> {
+        let mut sources: Vec<String> = vec![];
+
+        for source in self
+            .lines
+            .iter()
+            .filter_map(|message| message.source.as_ref())
+        {
+            if !sources.contains(source) {
+                sources.push(source.clone());
+            }
+        }
+
+        sources
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.lines.iter()
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Characters of the longest message, along with its timestamp.
+    #[cfg(feature = "tui")]
+    pub fn max_width(&self) -> usize {
+        self.lines
+            .iter()
+            .map(|message| {
+                message
+                    .timestamp
+                    .as_ref()
+                    .map_or(0, |timestamp| timestamp.chars().count())
+                    + message.text.chars().count()
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Absolute position after the last message.
+    pub fn end(&self) -> usize {
+        self.dropped + self.lines.len()
+    }
+}
+
+impl IntoIterator for Messages {
+    type Item = Message;
+    type IntoIter = std::collections::vec_deque::IntoIter<Message>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.lines.into_iter()
+    }
+}
+
+/// File the messages of a buffer are appended to, flushed at every line.
+/// Two log files are equal if they were opened at the same path.
+#[derive(Clone)]
+pub struct LogFile {
+    path: PathBuf,
+    file: Shared<LineWriter<File>>,
+}
+
+impl PartialEq for LogFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl LogFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| anyhow!("Failed to open log file '{}': {err}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Shared::new(LineWriter::new(file)),
+        })
+    }
+
+    fn write(&self, prefix: &str, line: &str) {
+        // A failing write must not stop the capture of the process.
+        let _ = self
+            .file
+            .write_with(|mut file| writeln!(file, "{prefix}{line}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(messages: &Messages) -> Vec<String> {
+        messages
+            .clone()
+            .into_iter()
+            .map(|message| message.text)
+            .collect()
+    }
+
+    #[test]
+    fn oldest_lines_are_dropped_past_max_lines() {
+        let mut messages = Messages::new(Some(3), Timestamps::None);
+
+        for line in ["a", "b", "c", "d", "e"] {
+            messages.push(Message::new(line.to_string(), Stream::Out));
+        }
+
+        assert_eq!(texts(&messages), ["c", "d", "e"]);
+
+        // Positions count the dropped lines, so they keep pointing to the same line.
+        assert_eq!(messages.end(), 5);
+
+        let mut unbounded = Messages::new(None, Timestamps::None);
+
+        for line in 0..100 {
+            unbounded.push(Message::new(line.to_string(), Stream::Out));
+        }
+
+        assert_eq!(unbounded.len(), 100);
+    }
+
+    #[test]
+    fn timestamps_are_prepended_to_the_lines() {
+        let stamp = Timestamps::Time.stamp(Instant::now()).unwrap();
+        let time = regex::Regex::new(r"^\[\d{2}:[0-5]\d:[0-5]\d\.\d{3}\] $").unwrap();
+        assert!(time.is_match(&stamp), "{stamp}");
+
+        let elapsed = Timestamps::ElapsedSinceStart.stamp(Instant::now()).unwrap();
+        assert!(
+            elapsed.starts_with("[+0.") && elapsed.ends_with("s] "),
+            "{elapsed}"
+        );
+
+        assert_eq!(Timestamps::None.stamp(Instant::now()), None);
+    }
+
+    #[test]
+    fn messages_are_stamped_when_pushed() {
+        let mut messages = Messages::new(None, Timestamps::ElapsedSinceStart);
+        messages.push(Message::new("line".to_string(), Stream::Out));
+
+        let stamp = messages.iter().next().unwrap().timestamp.clone();
+        assert!(stamp.is_some_and(|stamp| stamp.starts_with("[+0.")));
+
+        // The text itself is left as is, the stamp being drawn before it.
+        assert_eq!(texts(&messages), ["line"]);
+
+        let mut plain = Messages::new(None, Timestamps::None);
+        plain.push(Message::new("line".to_string(), Stream::Out));
+
+        assert_eq!(plain.iter().next().unwrap().timestamp, None);
+    }
+
+    #[test]
+    fn repeated_lines_are_collapsed() {
+        let mut messages = Messages::new(None, Timestamps::None);
+
+        for line in ["a", "a", "a", "b", "a"] {
+            if !messages.repeat_last(line, Stream::Out) {
+                messages.push(Message::new(line.to_string(), Stream::Out));
+            }
+        }
+
+        let repeats = messages
+            .iter()
+            .map(|message| (message.text.as_str(), message.repeated))
+            .collect::<Vec<_>>();
+
+        assert_eq!(repeats, [("a", 3), ("b", 1), ("a", 1)]);
+
+        // The same text from the other stream isn't a repeat.
+        assert!(!messages.repeat_last("a", Stream::Err));
+        assert_eq!(messages.len(), 3);
+    }
+
+    #[test]
+    fn last_line_is_replaced_from_the_same_stream() {
+        let mut messages = Messages::new(None, Timestamps::None);
+
+        messages.push(Message::new("10%".to_string(), Stream::Out));
+        messages.replace_last(Message::new("50%".to_string(), Stream::Out));
+        messages.replace_last(Message::new("error".to_string(), Stream::Err));
+
+        assert_eq!(texts(&messages), ["50%", "error"]);
+    }
+
+    #[test]
+    fn log_files_are_compared_by_path() {
+        let dir = std::env::temp_dir();
+        let first = dir.join(format!("process-terminal-{}-first.log", std::process::id()));
+        let second = dir.join(format!(
+            "process-terminal-{}-second.log",
+            std::process::id()
+        ));
+
+        let log = LogFile::open(&first).unwrap();
+
+        assert!(log == log.clone());
+        assert!(log == LogFile::open(&first).unwrap());
+        assert!(log != LogFile::open(&second).unwrap());
+
+        let _ = std::fs::remove_file(first);
+        let _ = std::fs::remove_file(second);
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+#[cfg(feature = "tui")]
+use std::time::Duration;
+
+/// Signal waking up the draw thread of a terminal when the state it shows changes. Its clones
+/// share the same signal.
+#[derive(Clone, Default)]
+pub(crate) struct Redraw(Arc<RedrawState>);
+
+#[derive(Default)]
+struct RedrawState {
+    requested: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Redraw {
+    pub fn request(&self) {
+        *self
+            .0
+            .requested
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = true;
+
+        self.0.condvar.notify_one();
+    }
+
+    /// Block until a redraw is requested or `timeout` expires, consuming the request.
+    #[cfg(feature = "tui")]
+    pub fn wait(&self, timeout: Duration) {
+        let requested = self
+            .0
+            .requested
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let (mut requested, _) = self
+            .0
+            .condvar
+            .wait_timeout_while(requested, timeout, |requested| !*requested)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        *requested = false;
+    }
+}
+
+/// Two signals are equal if they're clones of each other.
+impl PartialEq for Redraw {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,1120 @@
+use {
+    crate::{
+        messages::Messages,
+        status::{BaseStatus, DetachBaseStatus, FilterStatus, HelpLines, PaneAreas, ScrollStatus},
+        terminal::{
+            active_group, groups, BlockType, Decorations, DetachProcess, SharedMessages,
+            SharedProcesses, Stream,
+        },
+        Border, Level, MessageSettings, OnExit, PaneLayout, TerminalSettings, Theme,
+    },
+    crossterm::event::KeyCode,
+    ratatui::{
+        backend::TestBackend,
+        layout::{Constraint, Direction, Flex, Layout, Margin, Rect},
+        style::{Color, Modifier, Style, Stylize},
+        text::{Line, Span, Text},
+        widgets::{
+            Block, BorderType, Borders, Clear, List, ListState, Paragraph, Scrollbar,
+            ScrollbarOrientation, ScrollbarState, Tabs,
+        },
+        Frame,
+    },
+    std::{
+        borrow::Cow, collections::HashMap, ops::Range, process::ExitStatus, sync::Arc,
+        thread::sleep, time::Duration,
+    },
+};
+
+type DrawCacheDetach = DrawCache<Arc<Messages>, DetachBaseStatus, Vec<DetachProcess>>;
+
+/// Draw `data` on an in-memory screen of `width` x `height` cells, returning its rows.
+pub(crate) fn render_snapshot(
+    data: &DrawCache,
+    width: u16,
+    height: u16,
+    options: DrawOptions,
+    poll_interval: Duration,
+) -> String {
+    let mut terminal = ratatui::Terminal::new(TestBackend::new(width, height))
+        .expect("Failed to create terminal.");
+
+    let read = loop {
+        match data.try_detach(&mut Snapshots::default()) {
+            Some(read) => break read,
+            None => sleep(poll_interval),
+        }
+    };
+
+    terminal
+        .draw(|frame| {
+            draw_frame(frame, read, options);
+        })
+        .expect("Failed to draw on the test backend.");
+
+    let buffer = terminal.backend().buffer();
+
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Draw a snapshot of the terminal, with the help popup over it while it's open. Returns the
+/// area of each pane drawn, along with its focus index.
+pub(crate) fn draw_frame(
+    frame: &mut Frame,
+    mut read: DrawCacheDetach,
+    options: DrawOptions,
+) -> PaneAreas {
+    let help = read.main_scroll.help.take();
+
+    // The status bar takes the bottom row, below the panes.
+    let area = if options.status_bar {
+        let [area, bar] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+
+        render_status_bar(frame, bar, &read);
+
+        area
+    } else {
+        frame.area()
+    };
+
+    let groups = groups(read.processes.iter().map(|process| &process.settings));
+
+    // The tabs take the top row, above the panes, unless one is in full screen.
+    let area = if !groups.is_empty() && read.main_scroll.focus.is_none() {
+        let [bar, area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+
+        let selected = active_group(&groups, &read.main_scroll.group)
+            .and_then(|active| groups.iter().position(|group| group == active));
+
+        frame.render_widget(
+            Tabs::new(groups.clone())
+                .select(selected)
+                .highlight_style(Style::default().bold().reversed()),
+            bar,
+        );
+
+        area
+    } else {
+        area
+    };
+
+    let areas = draw_panes(frame, area, read, options);
+
+    if let Some(help) = help {
+        render_help(frame, &help);
+    }
+
+    areas
+}
+
+/// Draw the Main section next to the process panes, or the pane in full screen.
+fn draw_panes(
+    frame: &mut Frame,
+    area: Rect,
+    read: DrawCacheDetach,
+    options: DrawOptions,
+) -> PaneAreas {
+    let DrawOptions {
+        layout,
+        auto_colors,
+        scrollbar,
+        theme,
+        ..
+    } = options;
+
+    let DrawCache {
+        main_messages,
+        main_scroll,
+        processes,
+        ..
+    } = read;
+
+    let mut areas = vec![];
+
+    let (main_type, main_status) = if main_scroll.merged {
+        (BlockType::Merged, &main_scroll.merged_scroll)
+    } else {
+        (BlockType::Main, &main_scroll.main_scroll)
+    };
+
+    let (main_messages, main_title) = match &main_scroll.source {
+        Some(source) => (
+            Arc::new(main_messages.of_source(source)),
+            vec![format!("source '{source}'").yellow()],
+        ),
+        None => (main_messages, vec![]),
+    };
+
+    if let Some(focus) = main_scroll.focus {
+        areas.push((area, focus));
+
+        let filter = main_scroll.filter.filter(|filter| filter.focus == focus);
+
+        if focus == 0 {
+            render_frame(
+                frame,
+                area,
+                "",
+                main_type,
+                BlockFocus::Exit(filter.clone()),
+                &main_messages,
+                main_status,
+                &Decorations::default(),
+                None,
+                main_title,
+                false,
+                scrollbar,
+                theme,
+            );
+        } else if let Some((process, ty)) = focused_pane(processes, focus) {
+            let dimmed = grayed_out(&process);
+
+            let (messages, scroll) = match ty {
+                BlockType::Err => (&process.err_messages, &process.scroll_status_err),
+                _ => (&process.out_messages, &process.scroll_status_out),
+            };
+
+            render_frame(
+                frame,
+                area,
+                &process.name,
+                ty,
+                BlockFocus::Exit(filter),
+                messages,
+                scroll,
+                &process.decorations,
+                pane_accent(&process, auto_colors),
+                title_status(&process, messages, options),
+                dimmed,
+                scrollbar,
+                theme,
+            );
+        }
+    } else {
+        let groups = groups(processes.iter().map(|process| &process.settings));
+        let active = active_group(&groups, &main_scroll.group);
+
+        // Processes without messages have no pane. The panes of the other tabs keep their focus
+        // index, so it's the one of their keys.
+        let mut focus = 0;
+
+        let processes = processes
+            .into_iter()
+            .filter_map(|process| {
+                let first = focus;
+                focus += BlockType::of(&process.settings.messages).len();
+
+                let shown = process.settings.messages != MessageSettings::None
+                    && (process.settings.group.is_none()
+                        || process.settings.group.as_ref() == active);
+
+                shown.then_some((first, process))
+            })
+            .collect::<Vec<_>>();
+
+        let main_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(if processes.is_empty() {
+                vec![Constraint::Percentage(100)]
+            } else {
+                vec![Constraint::Percentage(30), Constraint::Percentage(70)]
+            })
+            .split(area);
+
+        render_frame(
+            frame,
+            main_chunks[0],
+            "",
+            main_type,
+            BlockFocus::Enter(0),
+            &main_messages,
+            main_status,
+            &Decorations::default(),
+            None,
+            main_title,
+            false,
+            scrollbar,
+            theme,
+        );
+
+        areas.push((main_chunks[0], 0));
+
+        if processes.is_empty() {
+            return areas;
+        }
+
+        let processes_chunks = split_panes(main_chunks[1], layout, processes.len());
+
+        for (index, (mut focus, process)) in processes.into_iter().enumerate() {
+            match process.settings.messages {
+                MessageSettings::Output => {
+                    focus += 1;
+
+                    render_frame(
+                        frame,
+                        processes_chunks[index],
+                        &process.name,
+                        BlockType::Out,
+                        BlockFocus::first(focus, process.settings.focus_key),
+                        &process.out_messages,
+                        &process.scroll_status_out,
+                        &process.decorations,
+                        pane_accent(&process, auto_colors),
+                        title_status(&process, &process.out_messages, options),
+                        grayed_out(&process),
+                        scrollbar,
+                        theme,
+                    );
+                    areas.push((processes_chunks[index], focus));
+                }
+                MessageSettings::Error => {
+                    focus += 1;
+
+                    render_frame(
+                        frame,
+                        processes_chunks[index],
+                        &process.name,
+                        BlockType::Err,
+                        BlockFocus::first(focus, process.settings.focus_key),
+                        &process.err_messages,
+                        &process.scroll_status_err,
+                        &process.decorations,
+                        pane_accent(&process, auto_colors),
+                        title_status(&process, &process.err_messages, options),
+                        grayed_out(&process),
+                        scrollbar,
+                        theme,
+                    );
+                    areas.push((processes_chunks[index], focus));
+                }
+                MessageSettings::All => {
+                    let process_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+                        .split(processes_chunks[index]);
+
+                    focus += 1;
+                    render_frame(
+                        frame,
+                        process_chunks[0],
+                        &process.name,
+                        BlockType::Out,
+                        BlockFocus::first(focus, process.settings.focus_key),
+                        &process.out_messages,
+                        &process.scroll_status_out,
+                        &process.decorations,
+                        pane_accent(&process, auto_colors),
+                        title_status(&process, &process.out_messages, options),
+                        grayed_out(&process),
+                        scrollbar,
+                        theme,
+                    );
+                    areas.push((process_chunks[0], focus));
+
+                    focus += 1;
+                    render_frame(
+                        frame,
+                        process_chunks[1],
+                        &process.name,
+                        BlockType::Err,
+                        BlockFocus::Enter(focus),
+                        &process.err_messages,
+                        &process.scroll_status_err,
+                        &process.decorations,
+                        pane_accent(&process, auto_colors),
+                        title_status(&process, &process.err_messages, options),
+                        grayed_out(&process),
+                        scrollbar,
+                        theme,
+                    );
+                    areas.push((process_chunks[1], focus));
+                }
+                MessageSettings::Combined => {
+                    focus += 1;
+
+                    render_frame(
+                        frame,
+                        processes_chunks[index],
+                        &process.name,
+                        BlockType::Combined,
+                        BlockFocus::first(focus, process.settings.focus_key),
+                        &process.out_messages,
+                        &process.scroll_status_out,
+                        &process.decorations,
+                        pane_accent(&process, auto_colors),
+                        title_status(&process, &process.out_messages, options),
+                        grayed_out(&process),
+                        scrollbar,
+                        theme,
+                    );
+                    areas.push((processes_chunks[index], focus));
+                }
+                MessageSettings::None => {}
+            }
+        }
+    }
+
+    areas
+}
+
+/// Draw the amount of processes, the focused pane and the message set with `set_status`.
+fn render_status_bar(frame: &mut Frame, area: Rect, read: &DrawCacheDetach) {
+    let count = read.processes.len();
+
+    let mut spans = vec![Span::raw(format!(
+        " {count} {}",
+        if count == 1 { "process" } else { "processes" }
+    ))];
+
+    let focus = match read.main_scroll.focus {
+        Some(0) => Some("Main".to_string()),
+        Some(focus) => focused_pane(read.processes.clone(), focus)
+            .map(|(process, ty)| format!("{} {}", process.name, ty.label())),
+        None => None,
+    };
+
+    if let Some(focus) = focus {
+        spans.push(Span::raw(" │ focus: "));
+        spans.push(Span::raw(focus).bold());
+    }
+
+    if let Some(text) = &read.main_scroll.status_text {
+        spans.push(Span::raw(" │ "));
+        spans.push(Span::raw(text.clone()).yellow());
+    }
+
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(Color::DarkGray)),
+        area,
+    );
+}
+
+/// Draw the bindings listed by the help popup, centered over the panes.
+fn render_help(frame: &mut Frame, lines: &HelpLines) {
+    const TITLE: &str = " Help - press '?' or 'Esc' to close ";
+
+    let keys_width = lines
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    let labels_width = lines
+        .iter()
+        .map(|(_, label)| label.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    // Borders on both sides, and two spaces between the keys and the labels.
+    let width = (keys_width + labels_width + 4).max(TITLE.len() + 2);
+
+    let [area] = Layout::horizontal([Constraint::Length(width as u16)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+
+    let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    let rows = lines.iter().map(|(keys, label)| {
+        Line::from(vec![
+            format!("{keys:<keys_width$}  ").yellow().bold(),
+            Span::raw(label.clone()),
+        ])
+    });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        List::new(rows).block(Block::bordered().title(Line::from(TITLE).bold().centered())),
+        area,
+    );
+}
+
+/// Spans appended to the name of a process in the title of its pane showing `messages`, as
+/// enabled by `TerminalSettings::pane_status` and `TerminalSettings::pane_stats`.
+fn title_status(
+    process: &DetachProcess,
+    messages: &Messages,
+    options: DrawOptions,
+) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+
+    if options.pane_status {
+        spans.push(Span::raw(" "));
+        spans.push(RunState::of(process.exit_status).symbol());
+        spans.push(format!(" ({} lines)", messages.len()).dark_gray());
+    }
+
+    if options.pane_stats {
+        spans.push(
+            format!(
+                " [{:.1} lines/s, {}]",
+                process.counters.rate,
+                format_bytes(process.counters.bytes)
+            )
+            .dark_gray(),
+        );
+    }
+
+    spans
+}
+
+/// `bytes` in the largest unit keeping at least one unit, e.g. `1.5 MB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Whether the panes of `process` are dimmed, as it exited with `OnExit::Gray`.
+fn grayed_out(process: &DetachProcess) -> bool {
+    process.settings.on_exit == OnExit::Gray && process.exit_status.is_some()
+}
+
+/// Colors assigned to the processes with `TerminalSettings::auto_colors`, in order.
+pub(crate) const PALETTE: [Color; 8] = [
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::LightCyan,
+    Color::LightMagenta,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::Green,
+];
+
+/// Accent of the panes of `process`: the one from its settings, or its palette one if
+/// `auto_colors` is set.
+fn pane_accent(process: &DetachProcess, auto_colors: bool) -> Option<Color> {
+    (auto_colors || process.settings.color.is_some()).then_some(process.accent)
+}
+
+/// Resolve a full screen focus index to the process and pane it targets.
+/// Indexes are assigned in order to the panes of each process, processes without messages
+/// having none.
+pub(crate) fn focused_pane(
+    processes: Vec<DetachProcess>,
+    focus: usize,
+) -> Option<(DetachProcess, BlockType)> {
+    let mut index = 0;
+
+    for process in processes {
+        for ty in BlockType::of(&process.settings.messages) {
+            index += 1;
+
+            if index == focus {
+                return Some((process, ty));
+            }
+        }
+    }
+
+    None
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_frame<N>(
+    frame: &mut Frame,
+    chunk: Rect,
+    name: N,
+    ty: BlockType,
+    focus: BlockFocus,
+    messages: &Messages,
+    scroll: &ScrollStatus,
+    decorations: &Decorations,
+    accent: Option<Color>,
+    status: Vec<Span<'static>>,
+    dimmed: bool,
+    scrollbar: bool,
+    theme: Theme,
+) where
+    N: ToString,
+{
+    let accent = if dimmed {
+        Some(Color::DarkGray)
+    } else {
+        accent
+    };
+
+    let mut title = vec![Span::raw(name.to_string())];
+    title.extend(status);
+
+    let title = Line::from(title).fg(accent.unwrap_or(Color::Gray)).bold();
+
+    let (messages, focus_txt) = match focus {
+        BlockFocus::Enter(index) if index <= 9 => {
+            (Cow::Borrowed(messages), format!("full screen: '{index}'"))
+        }
+        BlockFocus::Enter(_) => (Cow::Borrowed(messages), "full screen: 'Tab'".to_string()),
+        BlockFocus::Key(code) => (Cow::Borrowed(messages), format!("full screen: '{code}'")),
+        BlockFocus::Exit(None) => (
+            Cow::Borrowed(messages),
+            "press 'Esc' to exit full screen".to_string(),
+        ),
+        BlockFocus::Exit(Some(ref filter)) => {
+            let total = messages.len();
+            let messages = messages.filtered(&filter.text);
+
+            let focus_txt = if filter.editing {
+                format!(
+                    "/{}_ ({}/{total}) - press 'Enter' to apply",
+                    filter.text,
+                    messages.len()
+                )
+            } else {
+                format!(
+                    "filter '{}' ({}/{total}) - press 'Esc' to exit full screen",
+                    filter.text,
+                    messages.len()
+                )
+            };
+
+            (Cow::Owned(messages), focus_txt)
+        }
+    };
+
+    let highlight = match focus {
+        BlockFocus::Exit(Some(filter)) if !filter.text.is_empty() => {
+            regex::Regex::new(&regex::escape(&filter.text)).ok()
+        }
+        _ => decorations.highlight.clone(),
+    };
+
+    let sub_title = match ty {
+        BlockType::Main => Line::from("Main").cyan().bold(),
+        BlockType::Merged => Line::from("Merged").cyan().bold(),
+        BlockType::Out => Line::from("Out").light_green().bold(),
+        BlockType::Err => Line::from("Err").light_red().bold(),
+        BlockType::Combined => {
+            Line::from(vec!["Out".light_green(), " + ".gray(), "Err".light_red()]).bold()
+        }
+    };
+
+    let mut block = Block::default()
+        .title(title.alignment(theme.name))
+        .border_style(accent.map(Style::from).unwrap_or_default())
+        .title(sub_title.alignment(theme.subtitle))
+        .title(
+            Line::from(focus_txt)
+                .alignment(theme.hint)
+                .italic()
+                .dark_gray(),
+        );
+
+    if let Some(border_type) = border_type(theme.border) {
+        block = block.borders(Borders::ALL).border_type(border_type);
+    }
+
+    if dimmed {
+        block = block.style(Style::default().add_modifier(Modifier::DIM));
+    }
+
+    // Index of the message the view is anchored to, the last one when following the tail.
+    let anchor = match scroll.y {
+        Some(y) => {
+            let first = messages.end() - messages.len();
+
+            y.saturating_sub(first + 1)
+        }
+        None => messages.len().saturating_sub(1),
+    };
+
+    let is_scrolling = if let Some(y) = scroll.y {
+        let offset = messages.end().saturating_sub(y);
+
+        block = block.title(
+            Line::from(format!(
+                "Scrolling: offset {offset} - press 'shift + scroll_down' key to stop scrolling."
+            ))
+            .bold()
+            .left_aligned()
+            .yellow(),
+        );
+
+        true
+    } else {
+        false
+    };
+
+    let messages = messages
+        .iter()
+        .map(|message| {
+            // Only the Main section and combined panes mix both streams, so stderr lines need
+            // to stand out.
+            let style = match (&ty, message.level, message.stream) {
+                (_, Some(Level::Debug), _) => Style::default().dark_gray(),
+                (_, Some(Level::Info), _) => Style::default().gray(),
+                (_, Some(Level::Warn), _) => Style::default().yellow(),
+                (BlockType::Main | BlockType::Merged | BlockType::Combined, _, Stream::Err) => {
+                    Style::default().light_red()
+                }
+                // Lines matching the error pattern of the process.
+                (_, _, Stream::Out) if message.error => Style::default().light_red(),
+                _ => Style::default(),
+            };
+
+            let (text, styles) = match &message.timestamp {
+                Some(timestamp) => {
+                    let styles = message
+                        .styles
+                        .iter()
+                        .map(|&(offset, style)| (offset + timestamp.len(), style))
+                        .collect();
+
+                    (format!("{timestamp}{}", message.text), styles)
+                }
+                None => (message.text.clone(), message.styles.clone()),
+            };
+
+            let (text, styles) = if message.repeated > 1 {
+                let mut styles = styles;
+                styles.push((text.len(), Style::default().dark_gray()));
+
+                (
+                    format!("{text} (repeated {} times)", message.repeated),
+                    styles,
+                )
+            } else {
+                (text, styles)
+            };
+
+            let styles = match &highlight {
+                Some(highlight) => highlight_styles(
+                    styles,
+                    highlight
+                        .find_iter(&text)
+                        .filter(|found| !found.is_empty())
+                        .map(|found| found.range())
+                        .collect(),
+                ),
+                None => styles,
+            };
+
+            if !scroll.wrap {
+                // Clipped lines start from the horizontal scroll.
+                let start = text
+                    .char_indices()
+                    .nth(scroll.x as usize)
+                    .map_or(text.len(), |(index, _)| index);
+
+                return vec![Text::from(styled_line(&text[start..], start, &styles)).style(style)];
+            }
+
+            let messages = textwrap::wrap(&text, chunk.width.saturating_sub(3) as usize);
+
+            let leading_spaces = messages
+                .first()
+                .map(|first_message| {
+                    " ".repeat(first_message.chars().take_while(|&c| c == ' ').count())
+                })
+                .unwrap_or_default();
+
+            // Wrapped lines are slices of `text`, so their offset is where the styles start from.
+            let mut cursor = 0;
+
+            messages
+                .into_iter()
+                .enumerate()
+                .map(|(i, message)| {
+                    let start = cursor + text[cursor..].find(message.as_ref()).unwrap_or(0);
+                    cursor = start + message.len();
+
+                    let mut line = styled_line(&message, start, &styles);
+
+                    if i != 0 {
+                        line.spans.insert(0, Span::raw(leading_spaces.clone()));
+                    }
+
+                    Text::from(line).style(style)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // Messages can wrap on several rows, so the selection is the last row of the anchor message.
+    // Rows appended below it don't move the view.
+    let select_row = messages
+        .iter()
+        .take(anchor + 1)
+        .map(Vec::len)
+        .sum::<usize>()
+        .checked_sub(1);
+
+    let rows = messages.iter().map(Vec::len).sum::<usize>();
+
+    let mut state = ListState::default().with_selected(select_row);
+
+    let mut list = List::new(messages.into_iter().flatten());
+
+    if is_scrolling {
+        list = list.highlight_style(Style::default().yellow().bold());
+    }
+
+    let [header_chunk, list_chunk, footer_chunk] = Layout::vertical([
+        Constraint::Length(decorations.header.is_some() as u16),
+        Constraint::Fill(1),
+        Constraint::Length(decorations.footer.is_some() as u16),
+    ])
+    .areas(block.inner(chunk));
+
+    frame.render_widget(block, chunk);
+
+    if let Some(header) = &decorations.header {
+        frame.render_widget(header.clone(), header_chunk);
+    }
+
+    if let Some(footer) = &decorations.footer {
+        frame.render_widget(footer.clone(), footer_chunk);
+    }
+
+    frame.render_stateful_widget(list, list_chunk, &mut state);
+
+    // Drawn over the right border, only when the rows don't fit.
+    if scrollbar && rows > list_chunk.height as usize {
+        let mut scrollbar_state = ScrollbarState::new(rows)
+            .position(select_row.unwrap_or_default())
+            .viewport_content_length(list_chunk.height as usize);
+
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .style(accent.map(Style::from).unwrap_or_default()),
+            chunk.inner(Margin::new(0, 1)),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+fn border_type(border: Border) -> Option<BorderType> {
+    match border {
+        Border::Plain => Some(BorderType::Plain),
+        Border::Rounded => Some(BorderType::Rounded),
+        Border::Double => Some(BorderType::Double),
+        Border::Thick => Some(BorderType::Thick),
+        Border::None => None,
+    }
+}
+
+/// Split `area` into `count` chunks following `layout`.
+fn split_panes(area: Rect, layout: PaneLayout, count: usize) -> Vec<Rect> {
+    let split = |area: Rect, direction: Direction, count: usize| {
+        Layout::default()
+            .direction(direction)
+            .constraints(vec![Constraint::Ratio(1, count as u32); count])
+            .split(area)
+            .to_vec()
+    };
+
+    match layout {
+        PaneLayout::Horizontal => split(area, Direction::Horizontal, count),
+        PaneLayout::Vertical => split(area, Direction::Vertical, count),
+        PaneLayout::Grid { cols } => {
+            let cols = cols.clamp(1, count);
+
+            split(area, Direction::Vertical, count.div_ceil(cols))
+                .into_iter()
+                .flat_map(|row| split(row, Direction::Horizontal, cols))
+                .take(count)
+                .collect()
+        }
+    }
+}
+
+/// Add to `styles` the highlight of the byte `ranges` of a message.
+fn highlight_styles(styles: Vec<(usize, Style)>, ranges: Vec<Range<usize>>) -> Vec<(usize, Style)> {
+    if ranges.is_empty() {
+        return styles;
+    }
+
+    let mut offsets = styles
+        .iter()
+        .map(|(offset, _)| *offset)
+        .chain(ranges.iter().flat_map(|range| [range.start, range.end]))
+        .collect::<Vec<_>>();
+
+    offsets.sort();
+    offsets.dedup();
+
+    offsets
+        .into_iter()
+        .map(|offset| {
+            let style = styles
+                .iter()
+                .take_while(|(start, _)| *start <= offset)
+                .last()
+                .map(|(_, style)| *style)
+                .unwrap_or_default();
+
+            if ranges.iter().any(|range| range.contains(&offset)) {
+                (offset, style.patch(Style::default().black().on_yellow()))
+            } else {
+                (offset, style)
+            }
+        })
+        .collect()
+}
+
+/// Build the line of `text`, found at byte offset `start` of the whole message, splitting it
+/// into spans where the styles of the message change.
+fn styled_line(text: &str, start: usize, styles: &[(usize, Style)]) -> Line<'static> {
+    let end = start + text.len();
+
+    let mut style = Style::default();
+    let mut spans = vec![];
+    let mut from = start;
+
+    for &(offset, next_style) in styles {
+        if offset > from && offset < end {
+            spans.push(Span::styled(
+                text[from - start..offset - start].to_string(),
+                style,
+            ));
+            from = offset;
+        }
+
+        if offset >= end {
+            break;
+        }
+
+        style = next_style;
+    }
+
+    spans.push(Span::styled(text[from - start..].to_string(), style));
+
+    Line::from(spans)
+}
+
+enum BlockFocus {
+    Enter(usize),
+    /// Pane put in full screen by the focus key of its process.
+    Key(KeyCode),
+    /// Full screen pane, along with the filter applied to it.
+    Exit(Option<FilterStatus>),
+}
+
+impl BlockFocus {
+    /// Focus of the first pane of a process, bound to `focus_key` if it has one.
+    fn first(index: usize, focus_key: Option<KeyCode>) -> Self {
+        focus_key.map_or(BlockFocus::Enter(index), BlockFocus::Key)
+    }
+}
+
+/// What the frames show besides the panes, from the `TerminalSettings`.
+#[derive(Clone, Copy)]
+pub(crate) struct DrawOptions {
+    layout: PaneLayout,
+    auto_colors: bool,
+    pane_status: bool,
+    pane_stats: bool,
+    scrollbar: bool,
+    theme: Theme,
+    status_bar: bool,
+}
+
+impl DrawOptions {
+    pub fn new(settings: &TerminalSettings) -> Self {
+        Self {
+            layout: settings.layout,
+            auto_colors: settings.auto_colors,
+            pane_status: settings.pane_status,
+            pane_stats: settings.pane_stats,
+            scrollbar: settings.scrollbar,
+            theme: settings.theme,
+            status_bar: settings.status_bar,
+        }
+    }
+}
+
+/// Whether a process is running, shown in the title of its panes with
+/// `TerminalSettings::pane_status`.
+#[derive(Clone, Copy)]
+enum RunState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl RunState {
+    fn of(exit_status: Option<ExitStatus>) -> Self {
+        match exit_status {
+            None => RunState::Running,
+            Some(status) if status.success() => RunState::Succeeded,
+            Some(_) => RunState::Failed,
+        }
+    }
+
+    fn symbol(self) -> Span<'static> {
+        match self {
+            RunState::Running => "●".light_green(),
+            RunState::Succeeded => "✓".gray(),
+            RunState::Failed => "✗".light_red(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct DrawCache<MM = SharedMessages, MS = BaseStatus, P = SharedProcesses> {
+    /// Messages of the Main section, the merged timeline once detached if it's shown instead.
+    pub main_messages: MM,
+    pub merged_messages: MM,
+    pub main_scroll: MS,
+    pub processes: P,
+}
+
+impl DrawCache {
+    pub fn new(
+        main_messages: SharedMessages,
+        merged_messages: SharedMessages,
+        main_scroll: BaseStatus,
+        processes: SharedProcesses,
+    ) -> Self {
+        Self {
+            main_messages,
+            merged_messages,
+            main_scroll,
+            processes,
+        }
+    }
+
+    /// Snapshot of what the next frame shows. In full screen, only the focused pane's buffer is
+    /// snapshotted.
+    pub fn try_detach(&self, snapshots: &mut Snapshots) -> Option<DrawCacheDetach> {
+        let main_scroll = self.main_scroll.try_detach()?;
+        let focus = main_scroll.focus;
+
+        let main_messages = if focus.is_none_or(|focus| focus == 0) {
+            snapshots.get(if main_scroll.merged {
+                &self.merged_messages
+            } else {
+                &self.main_messages
+            })?
+        } else {
+            Arc::default()
+        };
+
+        let mut pane = 0;
+
+        let processes = self
+            .processes
+            .try_read_access()?
+            .iter()
+            .map(|process| {
+                process.try_detach(snapshots, || {
+                    pane += 1;
+                    focus.is_none_or(|focus| focus == pane)
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        snapshots.rotate();
+
+        Some(DrawCache {
+            main_messages,
+            merged_messages: Arc::default(),
+            main_scroll,
+            processes,
+        })
+    }
+}
+
+/// Snapshots of the buffers drawn by the last frames, by generation, so that a buffer is only
+/// cloned again once it changes.
+#[derive(Default)]
+pub(crate) struct Snapshots {
+    previous: HashMap<u64, Arc<Messages>>,
+    current: HashMap<u64, Arc<Messages>>,
+}
+
+impl Snapshots {
+    /// Snapshot of `messages`, `None` instead of blocking if it's being written.
+    pub fn get(&mut self, messages: &SharedMessages) -> Option<Arc<Messages>> {
+        let messages = messages.try_read_access()?;
+        let generation = messages.generation();
+
+        let snapshot = self
+            .current
+            .get(&generation)
+            .or_else(|| self.previous.get(&generation))
+            .cloned()
+            .unwrap_or_else(|| Arc::new(messages.clone()));
+
+        self.current.insert(generation, snapshot.clone());
+
+        Some(snapshot)
+    }
+
+    /// Forget the snapshots not taken since the last rotation.
+    pub fn rotate(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{terminal::Process, ProcessSettings},
+        std::process::Command,
+    };
+
+    #[test]
+    fn exited_processes_are_grayed_out() {
+        let status = Command::new("true").status().unwrap();
+        let exited = |on_exit| {
+            let settings = ProcessSettings::builder().on_exit(on_exit).build();
+            let mut process = Process::new("Exited".to_string(), settings)
+                .try_detach(&mut Snapshots::default(), || true)
+                .unwrap();
+            process.exit_status = Some(status);
+            process
+        };
+
+        assert!(grayed_out(&exited(OnExit::Gray)));
+        assert!(!grayed_out(&exited(OnExit::Keep)));
+        assert!(!grayed_out(&detached("Running", MessageSettings::Output)));
+    }
+
+    fn detached(name: &str, messages: MessageSettings) -> DetachProcess {
+        Process::new(name.to_string(), ProcessSettings::new(messages))
+            .try_detach(&mut Snapshots::default(), || true)
+            .unwrap()
+    }
+
+    #[test]
+    fn focus_skips_processes_without_panes() {
+        let processes = vec![
+            detached("A", MessageSettings::None),
+            detached("B", MessageSettings::Output),
+            detached("C", MessageSettings::None),
+            detached("D", MessageSettings::Error),
+            detached("E", MessageSettings::None),
+        ];
+
+        let resolve =
+            |focus| focused_pane(processes.clone(), focus).map(|(process, ty)| (process.name, ty));
+
+        assert_eq!(resolve(0), None);
+        assert_eq!(resolve(1), Some(("B".to_string(), BlockType::Out)));
+        assert_eq!(resolve(2), Some(("D".to_string(), BlockType::Err)));
+        assert_eq!(resolve(3), None);
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::{
+    fs::File,
+    io::{self, stdout, Write},
+    sync::{Mutex, PoisonError},
+};
+
+/// Copy of the stdout of the program, kept while it's captured into the Main section.
+static SAVED_STDOUT: Mutex<Option<File>> = Mutex::new(None);
+
+/// Writer drawing on the screen: stdout, or its copy while stdout is captured.
+pub(crate) struct Screen;
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut *SAVED_STDOUT.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(saved) => saved.write(buf),
+            None => stdout().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut *SAVED_STDOUT.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(saved) => saved.flush(),
+            None => stdout().flush(),
+        }
+    }
+}
+
+/// Redirect the stdout of the program into a pipe, returning its read end. The screen is then
+/// drawn on a copy of the previous stdout.
+#[cfg(unix)]
+pub(crate) fn capture_stdout() -> io::Result<File> {
+    stdout().flush()?;
+
+    let mut fds = [0; 2];
+
+    // SAFETY: `pipe` writes two descriptors into the array, each owned by a single `File` below.
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let [read, write] = fds.map(|fd| unsafe { File::from_raw_fd(fd) });
+
+    // SAFETY: the descriptor returned by `dup` is new, so owned by this `File` only.
+    let saved = match unsafe { libc::dup(libc::STDOUT_FILENO) } {
+        fd if fd < 0 => return Err(io::Error::last_os_error()),
+        fd => unsafe { File::from_raw_fd(fd) },
+    };
+
+    // SAFETY: both descriptors are open. The write end stays open as stdout once `write` drops.
+    if unsafe { libc::dup2(write.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    *SAVED_STDOUT.lock().unwrap_or_else(PoisonError::into_inner) = Some(saved);
+
+    Ok(read)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn capture_stdout() -> io::Result<File> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "stdout can only be captured on Unix",
+    ))
+}
+
+/// Point stdout back to the screen, ending its capture. Harmless if it isn't captured.
+pub(crate) fn restore_stdout() {
+    let saved = SAVED_STDOUT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+
+    #[cfg(unix)]
+    if let Some(saved) = saved {
+        // SAFETY: both descriptors are open, `saved` being closed only once duplicated. The
+        // pipe is closed along with stdout, ending its capture thread.
+        unsafe { libc::dup2(saved.as_raw_fd(), libc::STDOUT_FILENO) };
+    }
+
+    #[cfg(not(unix))]
+    drop(saved);
+}
@@ -0,0 +1,369 @@
+use {
+    crate::OnExit,
+    serde::{
+        de::{self, MapAccess, Visitor},
+        ser, Deserialize, Deserializer, Serialize, Serializer,
+    },
+    std::{fmt, time::Duration},
+};
+
+#[cfg(feature = "tui")]
+use {
+    crate::{key_name, parse_key, KeyAction},
+    crossterm::event::{KeyCode, KeyModifiers},
+    ratatui::layout::Alignment,
+    serde::de::SeqAccess,
+};
+
+/// Durations as an amount of milliseconds, e.g. `refresh_ms = 100`.
+pub(crate) mod millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        u64::try_from(duration.as_millis())
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// Keys by their name, e.g. `"Left"`, `"F1"` or `"a"`, as read by `parse_key`.
+#[cfg(feature = "tui")]
+pub(crate) mod key_code {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(code: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        name::<S>(*code)?.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        parse_key(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+
+    pub(super) fn name<S: Serializer>(code: KeyCode) -> Result<String, S::Error> {
+        key_name(code).ok_or_else(|| ser::Error::custom(format!("key {code:?} has no name")))
+    }
+}
+
+/// Like `key_code`, for an optional key.
+#[cfg(feature = "tui")]
+pub(crate) mod optional_key_code {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        code: &Option<KeyCode>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        code.map(key_code::name::<S>)
+            .transpose()?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<KeyCode>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|name| parse_key(&name).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Modifiers joined with `+`, e.g. `"Ctrl+Alt"`, empty for none.
+#[cfg(feature = "tui")]
+pub(crate) mod modifiers {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        modifiers: &KeyModifiers,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        prefix(*modifiers)
+            .trim_end_matches('+')
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<KeyModifiers, D::Error> {
+        let names = String::deserialize(deserializer)?;
+
+        names.split('+').filter(|name| !name.is_empty()).try_fold(
+            KeyModifiers::empty(),
+            |modifiers, name| {
+                MODIFIERS
+                    .iter()
+                    .find(|(modifier, _)| modifier.eq_ignore_ascii_case(name))
+                    .map(|(_, modifier)| modifiers | *modifier)
+                    .ok_or_else(|| de::Error::unknown_variant(name, &["Ctrl", "Alt", "Shift"]))
+            },
+        )
+    }
+}
+
+/// Key bindings as a table of the keys of each action, written like `Ctrl+q`, e.g.
+/// `next_error = ["n", "Alt+Down"]`.
+#[cfg(feature = "tui")]
+pub(crate) mod key_bindings {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bindings: &[(KeyAction, KeyCode, KeyModifiers)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut actions: Vec<(KeyAction, Vec<String>)> = vec![];
+
+        for (action, code, modifiers) in bindings {
+            let binding = binding_name::<S>(*code, *modifiers)?;
+
+            match actions.iter_mut().find(|(bound, _)| bound == action) {
+                Some((_, keys)) => keys.push(binding),
+                None => actions.push((*action, vec![binding])),
+            }
+        }
+
+        serializer.collect_map(actions)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(KeyAction, KeyCode, KeyModifiers)>, D::Error> {
+        deserializer.deserialize_map(BindingsVisitor)
+    }
+
+    struct BindingsVisitor;
+
+    impl<'de> Visitor<'de> for BindingsVisitor {
+        type Value = Vec<(KeyAction, KeyCode, KeyModifiers)>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a table of the keys of each action")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut bindings = vec![];
+
+            while let Some((action, Keys(keys))) = map.next_entry::<KeyAction, Keys>()? {
+                bindings.extend(
+                    keys.into_iter()
+                        .map(|Key(code, modifiers)| (action, code, modifiers)),
+                );
+            }
+
+            Ok(bindings)
+        }
+    }
+
+    /// Keys of an action, a single one or an array of them.
+    struct Keys(Vec<Key>);
+
+    impl<'de> Deserialize<'de> for Keys {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(KeysVisitor)
+        }
+    }
+
+    struct KeysVisitor;
+
+    impl<'de> Visitor<'de> for KeysVisitor {
+        type Value = Keys;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a key or an array of them")
+        }
+
+        fn visit_str<E: de::Error>(self, binding: &str) -> Result<Keys, E> {
+            Key::parse(binding).map(|key| Keys(vec![key]))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Keys, A::Error> {
+            let mut keys = vec![];
+
+            while let Some(key) = seq.next_element()? {
+                keys.push(key);
+            }
+
+            Ok(Keys(keys))
+        }
+    }
+
+    struct Key(KeyCode, KeyModifiers);
+
+    impl Key {
+        fn parse<E: de::Error>(binding: &str) -> Result<Self, E> {
+            parse_binding(binding)
+                .map(|(code, modifiers)| Key(code, modifiers))
+                .map_err(de::Error::custom)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Key {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Key::parse(&String::deserialize(deserializer)?)
+        }
+    }
+}
+
+/// Alignments by their lowercase name, e.g. `"center"`.
+#[cfg(feature = "tui")]
+pub(crate) mod alignment {
+    use super::*;
+
+    const ALIGNMENTS: [(&str, Alignment); 3] = [
+        ("left", Alignment::Left),
+        ("center", Alignment::Center),
+        ("right", Alignment::Right),
+    ];
+
+    pub fn serialize<S: Serializer>(
+        alignment: &Alignment,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ALIGNMENTS
+            .iter()
+            .find(|(_, named)| named == alignment)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Alignment, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        ALIGNMENTS
+            .iter()
+            .find(|(named, _)| *named == name)
+            .map(|(_, alignment)| *alignment)
+            .ok_or_else(|| de::Error::unknown_variant(&name, &["left", "center", "right"]))
+    }
+}
+
+#[cfg(feature = "tui")]
+const MODIFIERS: [(&str, KeyModifiers); 3] = [
+    ("Ctrl", KeyModifiers::CONTROL),
+    ("Alt", KeyModifiers::ALT),
+    ("Shift", KeyModifiers::SHIFT),
+];
+
+/// Names of `modifiers`, each followed by `+`.
+#[cfg(feature = "tui")]
+fn prefix(modifiers: KeyModifiers) -> String {
+    MODIFIERS
+        .iter()
+        .filter(|(_, modifier)| modifiers.contains(*modifier))
+        .map(|(name, _)| format!("{name}+"))
+        .collect()
+}
+
+/// Key along with its modifiers, written like `Ctrl+q`. An uppercase character implies Shift.
+#[cfg(feature = "tui")]
+fn parse_binding(binding: &str) -> anyhow::Result<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::empty();
+    let mut rest = binding;
+
+    'modifiers: loop {
+        for (name, modifier) in MODIFIERS {
+            let Some(key) = rest
+                .get(..name.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(name))
+                .and_then(|_| rest[name.len()..].strip_prefix('+'))
+                .filter(|key| !key.is_empty())
+            else {
+                continue;
+            };
+
+            modifiers |= modifier;
+            rest = key;
+            continue 'modifiers;
+        }
+
+        break;
+    }
+
+    let code = parse_key(rest)?;
+
+    // Reported along with Shift by the terminal.
+    if matches!(code, KeyCode::Char(char) if char.is_uppercase()) {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+
+    Ok((code, modifiers))
+}
+
+/// Binding read back by `parse_binding`, the Shift of an uppercase character being implied.
+#[cfg(feature = "tui")]
+fn binding_name<S: Serializer>(code: KeyCode, modifiers: KeyModifiers) -> Result<String, S::Error> {
+    let modifiers = match code {
+        KeyCode::Char(char) if char.is_uppercase() => modifiers - KeyModifiers::SHIFT,
+        _ => modifiers,
+    };
+
+    Ok(format!(
+        "{}{}",
+        prefix(modifiers),
+        key_code::name::<S>(code)?
+    ))
+}
+
+/// `"keep"`, `"gray"`, `"remove"` to remove the process right away, or
+/// `{ remove_after_ms = 5000 }`.
+impl Serialize for OnExit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OnExit::Keep => serializer.serialize_str("keep"),
+            OnExit::Gray => serializer.serialize_str("gray"),
+            OnExit::Remove(delay) if delay.is_zero() => serializer.serialize_str("remove"),
+            OnExit::Remove(delay) => {
+                serializer.collect_map([("remove_after_ms", delay.as_millis() as u64)])
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OnExit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(OnExitVisitor)
+    }
+}
+
+struct OnExitVisitor;
+
+impl<'de> Visitor<'de> for OnExitVisitor {
+    type Value = OnExit;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("\"keep\", \"gray\", \"remove\" or { remove_after_ms = <delay> }")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<OnExit, E> {
+        match value {
+            "keep" => Ok(OnExit::Keep),
+            "gray" => Ok(OnExit::Gray),
+            "remove" => Ok(OnExit::Remove(Duration::ZERO)),
+            _ => Err(de::Error::unknown_variant(
+                value,
+                &["keep", "gray", "remove"],
+            )),
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OnExit, A::Error> {
+        let mut delay = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "remove_after_ms" if delay.is_none() => {
+                    delay = Some(Duration::from_millis(map.next_value()?))
+                }
+                "remove_after_ms" => return Err(de::Error::duplicate_field("remove_after_ms")),
+                _ => return Err(de::Error::unknown_field(&key, &["remove_after_ms"])),
+            }
+        }
+
+        delay
+            .map(OnExit::Remove)
+            .ok_or_else(|| de::Error::missing_field("remove_after_ms"))
+    }
+}
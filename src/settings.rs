@@ -1,53 +1,948 @@
-use crossterm::event::KeyCode;
+use std::{path::PathBuf, process::Child, sync::Arc, time::Duration};
+
+#[cfg(feature = "tui")]
+use {
+    crossterm::event::{KeyCode, KeyModifiers},
+    ratatui::{layout::Alignment, style::Color},
+};
+
+/// Default amount of lines kept for each pane before the oldest ones are dropped.
+pub const DEFAULT_MAX_LINES: usize = 10_000;
+
+/// Default minimum interval between two redraws of the terminal.
+pub const DEFAULT_REFRESH: Duration = Duration::from_millis(50);
+
+/// Default delay before the first restart of a process by its `RestartPolicy`.
+pub const DEFAULT_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Default interval between two checks of the loops waiting on a process (searches, exits).
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct ProcessSettings {
     pub messages: MessageSettings,
+    #[cfg(feature = "tui")]
     pub scroll: ScrollSettings,
-    pub clear_regex: bool,
+    /// What is stripped from the lines, the SGR color codes by default.
+    pub clear_regex: CleanMode,
+    /// Maximum amount of lines kept for each pane of the process, `None` for unbounded.
+    pub max_lines: Option<usize>,
+    pub timestamps: Timestamps,
+    /// Render the SGR color codes of the lines instead of stripping them.
+    pub ansi_colors: bool,
+    /// File the captured lines are appended to, in addition to being displayed.
+    pub log_to: Option<PathBuf>,
+    /// Wrap the long lines to the pane width, instead of clipping them. Toggled with `w`.
+    pub wrap: bool,
+    /// Color of the borders and name of the panes.
+    #[cfg(feature = "tui")]
+    pub color: Option<Color>,
+    /// Collapse the consecutive identical lines into one, with a count of its repeats.
+    pub collapse_repeats: bool,
+    /// Let a line ended by `\r` be overwritten by the next one, so progress bars animate in place.
+    pub carriage_return: bool,
+    /// Regex marking the lines as errors, along with the stderr ones, to jump between them with
+    /// `n`/`N`.
+    pub error_pattern: Option<String>,
+    /// Closure applied to each captured line once cleaned, dropping the line when it returns
+    /// `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub transform: Option<LineTransform>,
+    /// Characters kept of each captured line, the rest being replaced by `…`, `None` to keep the
+    /// whole lines.
+    pub max_line_length: Option<usize>,
+    /// What becomes of the panes of the process once it exits.
+    pub on_exit: OnExit,
+    /// Lines stored when the process floods its output, the others being dropped.
+    pub throttle: Throttle,
+    /// When the process is spawned again with `respawn` once it exits.
+    pub restart: RestartPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub respawn: Option<Respawn>,
+    /// Delay before a restart, doubled after each one up to a minute, and reset once a child
+    /// runs for a minute.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "restart_delay_ms", with = "crate::serialize::millis")
+    )]
+    pub restart_delay: Duration,
+    /// Key showing the first pane of the process in full screen, instead of its digit.
+    #[cfg(feature = "tui")]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::optional_key_code"))]
+    pub focus_key: Option<KeyCode>,
+    /// Tab the panes of the process are shown in, along with the ones without a group.
+    pub group: Option<String>,
+    /// Rewrite the lines holding a JSON object into `key=value` pairs.
+    #[cfg(feature = "json")]
+    pub json_lines: Option<JsonLines>,
+}
+
+/// Same as `ProcessSettings::builder().build()`.
+impl Default for ProcessSettings {
+    fn default() -> Self {
+        Self::builder().build()
+    }
 }
 
 impl ProcessSettings {
+    pub fn builder() -> ProcessSettingsBuilder {
+        ProcessSettingsBuilder::default()
+    }
+
     pub fn new(messages: MessageSettings) -> Self {
-        Self {
-            messages,
-            scroll: ScrollSettings::Disable,
-            clear_regex: true,
-        }
+        Self::builder().messages(messages).build()
     }
 
+    #[cfg(feature = "tui")]
     pub fn new_with_scroll(messages: MessageSettings, scroll: ScrollSettings) -> Self {
-        Self {
-            messages,
-            scroll,
-            clear_regex: true,
-        }
+        Self::builder().messages(messages).scroll(scroll).build()
+    }
+
+    /// Builder starting from these settings, e.g. the defaults of the terminal.
+    pub fn into_builder(self) -> ProcessSettingsBuilder {
+        ProcessSettingsBuilder { settings: self }
     }
 
     pub fn disable_clear_regex(self) -> Self {
         Self {
-            clear_regex: false,
+            clear_regex: CleanMode::Off,
             ..self
         }
     }
+
+    #[deprecated(note = "use `ProcessSettings::builder().wrap(false)`")]
+    pub fn disable_wrap(self) -> Self {
+        self.into_builder().wrap(false).build()
+    }
+
+    #[deprecated(note = "use `ProcessSettings::builder().max_lines(..)` or `.no_max_lines()`")]
+    pub fn with_max_lines(self, max_lines: Option<usize>) -> Self {
+        Self { max_lines, ..self }
+    }
+
+    #[deprecated(note = "use `ProcessSettings::builder().timestamps(..)`")]
+    pub fn with_timestamps(self, timestamps: Timestamps) -> Self {
+        self.into_builder().timestamps(timestamps).build()
+    }
+
+    #[deprecated(note = "use `ProcessSettings::builder().ansi_colors(true)`")]
+    pub fn with_ansi_colors(self) -> Self {
+        self.into_builder().ansi_colors(true).build()
+    }
+
+    #[deprecated(note = "use `ProcessSettings::builder().log_to(..)`")]
+    pub fn with_log_to<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.into_builder().log_to(path).build()
+    }
+}
+
+/// Closure rewriting or dropping the captured lines of a process.
+/// Two transforms are equal when they're the same closure.
+#[derive(Clone)]
+pub struct LineTransform(Arc<dyn Fn(String) -> Option<String> + Send + Sync>);
+
+impl PartialEq for LineTransform {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl LineTransform {
+    pub fn new<F>(transform: F) -> Self
+    where
+        F: Fn(String) -> Option<String> + Send + Sync + 'static,
+    {
+        Self(Arc::new(transform))
+    }
+
+    pub fn apply(&self, line: String) -> Option<String> {
+        (self.0)(line)
+    }
+}
+
+/// Closure spawning a new child for a process restarted by its `RestartPolicy`.
+/// Two respawns are equal when they're the same closure.
+#[derive(Clone)]
+pub struct Respawn(Arc<dyn Fn() -> anyhow::Result<Child> + Send + Sync>);
+
+impl PartialEq for Respawn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Respawn {
+    pub fn new<F>(respawn: F) -> Self
+    where
+        F: Fn() -> anyhow::Result<Child> + Send + Sync + 'static,
+    {
+        Self(Arc::new(respawn))
+    }
+
+    pub fn spawn(&self) -> anyhow::Result<Child> {
+        (self.0)()
+    }
+}
+
+/// Builder of `ProcessSettings`, starting from the defaults: output messages only, no scroll
+/// keys, escape sequences stripped, `DEFAULT_MAX_LINES` lines kept, wrapped lines and every
+/// repeated line shown, in full.
+#[derive(Clone)]
+pub struct ProcessSettingsBuilder {
+    settings: ProcessSettings,
+}
+
+impl Default for ProcessSettingsBuilder {
+    fn default() -> Self {
+        Self {
+            settings: ProcessSettings {
+                messages: MessageSettings::Output,
+                #[cfg(feature = "tui")]
+                scroll: ScrollSettings::Disable,
+                clear_regex: CleanMode::Ansi,
+                max_lines: Some(DEFAULT_MAX_LINES),
+                timestamps: Timestamps::None,
+                ansi_colors: false,
+                log_to: None,
+                wrap: true,
+                #[cfg(feature = "tui")]
+                color: None,
+                collapse_repeats: false,
+                carriage_return: false,
+                error_pattern: None,
+                transform: None,
+                max_line_length: None,
+                on_exit: OnExit::Keep,
+                throttle: Throttle::Off,
+                restart: RestartPolicy::Never,
+                respawn: None,
+                restart_delay: DEFAULT_RESTART_DELAY,
+                #[cfg(feature = "tui")]
+                focus_key: None,
+                group: None,
+                #[cfg(feature = "json")]
+                json_lines: None,
+            },
+        }
+    }
+}
+
+impl ProcessSettingsBuilder {
+    pub fn messages(mut self, messages: MessageSettings) -> Self {
+        self.settings.messages = messages;
+        self
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn scroll(mut self, scroll: ScrollSettings) -> Self {
+        self.settings.scroll = scroll;
+        self
+    }
+
+    /// What is stripped from the lines. With `CleanMode::Custom`, the matches of the pattern are
+    /// stripped along with the SGR color codes.
+    pub fn clear_regex(mut self, clear_regex: CleanMode) -> Self {
+        self.settings.clear_regex = clear_regex;
+        self
+    }
+
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.settings.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Keep every line of the panes, instead of dropping the oldest ones past `max_lines`.
+    pub fn no_max_lines(mut self) -> Self {
+        self.settings.max_lines = None;
+        self
+    }
+
+    pub fn timestamps(mut self, timestamps: Timestamps) -> Self {
+        self.settings.timestamps = timestamps;
+        self
+    }
+
+    /// Parse the SGR color codes into styles. Any other escape sequence (e.g. cursor movements)
+    /// is still stripped.
+    pub fn ansi_colors(mut self, ansi_colors: bool) -> Self {
+        self.settings.ansi_colors = ansi_colors;
+        self
+    }
+
+    pub fn log_to<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.settings.log_to = Some(path.into());
+        self
+    }
+
+    /// Wrap the long lines, or clip them when false, allowing to scroll them horizontally.
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.settings.wrap = wrap;
+        self
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn color(mut self, color: Color) -> Self {
+        self.settings.color = Some(color);
+        self
+    }
+
+    /// Show the consecutive identical lines once, followed by `(repeated N times)`.
+    pub fn collapse_repeats(mut self, collapse_repeats: bool) -> Self {
+        self.settings.collapse_repeats = collapse_repeats;
+        self
+    }
+
+    /// Split the lines on `\r` too, each line ended by `\r` being overwritten by the next one,
+    /// as a terminal does with progress bars.
+    pub fn carriage_return(mut self, carriage_return: bool) -> Self {
+        self.settings.carriage_return = carriage_return;
+        self
+    }
+
+    /// Mark the lines matching the regex `pattern` as errors. Fails when adding the process if
+    /// the pattern is invalid.
+    pub fn error_pattern<S: ToString>(mut self, pattern: S) -> Self {
+        self.settings.error_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Show the lines holding a JSON object as `key=value` pairs, leaving the other lines as
+    /// they are. The color codes of a rewritten line are lost.
+    #[cfg(feature = "json")]
+    pub fn json_lines(mut self, json_lines: JsonLines) -> Self {
+        self.settings.json_lines = Some(json_lines);
+        self
+    }
+
+    pub fn on_exit(mut self, on_exit: OnExit) -> Self {
+        self.settings.on_exit = on_exit;
+        self
+    }
+
+    /// Keep the terminal responsive when the process floods its output, storing only part of
+    /// the lines. Every line is still forwarded to the subscribers and the line tap, and matched
+    /// by the searches, while the merged timeline only shows the lines stored.
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        self.settings.throttle = throttle;
+        self
+    }
+
+    /// When the process is spawned again with `respawn` once it exits.
+    pub fn restart(mut self, restart: RestartPolicy) -> Self {
+        self.settings.restart = restart;
+        self
+    }
+
+    pub fn respawn<F>(mut self, respawn: F) -> Self
+    where
+        F: Fn() -> anyhow::Result<Child> + Send + Sync + 'static,
+    {
+        self.settings.respawn = Some(Respawn::new(respawn));
+        self
+    }
+
+    pub fn restart_delay(mut self, restart_delay: Duration) -> Self {
+        self.settings.restart_delay = restart_delay;
+        self
+    }
+
+    /// Bind `focus_key`, e.g. `KeyCode::F(1)` or `KeyCode::Char('a')`, to the full screen of the
+    /// first pane of the process, in place of its digit. Past the ninth pane, that's the only
+    /// key reaching it besides Tab.
+    #[cfg(feature = "tui")]
+    pub fn focus_key(mut self, focus_key: KeyCode) -> Self {
+        self.settings.focus_key = Some(focus_key);
+        self
+    }
+
+    /// Show the process only in the tab of `group`, switched with `[`/`]`. The processes
+    /// without a group are shown in every tab.
+    pub fn group<S: ToString>(mut self, group: S) -> Self {
+        self.settings.group = Some(group.to_string());
+        self
+    }
+
+    /// Cut the captured lines longer than `max_line_length` characters, ending them with `…`.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.settings.max_line_length = Some(max_line_length);
+        self
+    }
+
+    /// Rewrite each captured line with `transform`, after its escape sequences are handled,
+    /// dropping it when `None` is returned. The color codes of a rewritten line are lost.
+    pub fn transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(String) -> Option<String> + Send + Sync + 'static,
+    {
+        self.settings.transform = Some(LineTransform::new(transform));
+        self
+    }
+
+    pub fn build(self) -> ProcessSettings {
+        self.settings
+    }
 }
 
 #[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum MessageSettings {
     None,
     Output,
     Error,
     All,
+    /// Output and error messages interleaved in a single pane, in arrival order, with errors
+    /// highlighted. Searches match the error messages too.
+    Combined,
+}
+
+/// Formatting of the JSON lines of a process, e.g. `{"level":"info","ctx":{"id":3}}` being shown
+/// as `level=info ctx.id=3`.
+#[cfg(feature = "json")]
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct JsonLines {
+    /// Color of the keys, `None` to leave them unstyled.
+    #[cfg(feature = "tui")]
+    pub key_color: Option<Color>,
+}
+
+/// Lines of a process stored in its panes when it floods its output.
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Throttle {
+    /// Store every line.
+    #[default]
+    Off,
+    /// Store at most `lines` lines for each `interval`, e.g. the refresh rate of the terminal.
+    /// The amount of lines dropped is noted in the pane before the first line stored after the
+    /// interval, or once the output ends if no line follows.
+    Burst {
+        lines: usize,
+        #[cfg_attr(
+            feature = "serde",
+            serde(rename = "interval_ms", with = "crate::serialize::millis")
+        )]
+        interval: Duration,
+    },
+    /// Store one line every `every` lines.
+    Sample { every: usize },
+}
+
+/// What becomes of the panes of a process once it exits.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum OnExit {
+    /// Leave the panes as they are.
+    #[default]
+    Keep,
+    /// Dim the panes and their title, until the process is restarted.
+    Gray,
+    /// Remove the process after the delay, unless it's restarted meanwhile.
+    Remove(Duration),
+}
+
+/// When a process with a `respawn` closure is spawned again once it exits. A process stopped by
+/// closing the terminal, or removed, isn't restarted.
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    Always,
+    /// Only when it fails, i.e. exits with a non-zero status or is killed.
+    OnFailure,
+    /// Like `OnFailure`, at most `n` times.
+    UpTo(usize),
+}
+
+impl RestartPolicy {
+    /// Whether a process restarted `restarts` times is restarted again, once exited.
+    pub(crate) fn fires(self, success: bool, restarts: usize) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => !success,
+            RestartPolicy::UpTo(n) => !success && restarts < n,
+        }
+    }
+}
+
+/// Arrangement of the process panes. The fullscreen mode is not affected.
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PaneLayout {
+    /// One column for each process.
+    #[default]
+    Horizontal,
+    /// One row for each process.
+    Vertical,
+    /// Processes laid out row by row, `cols` processes for each row.
+    Grid { cols: usize },
+}
+
+/// Look of the pane borders and of the titles on their top border.
+#[cfg(feature = "tui")]
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct Theme {
+    pub border: Border,
+    /// Alignment of the process name and of its status.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::alignment"))]
+    pub name: Alignment,
+    /// Alignment of the stream shown by the pane, e.g. `Out`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::alignment"))]
+    pub subtitle: Alignment,
+    /// Alignment of the hint of the full screen key.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::alignment"))]
+    pub hint: Alignment,
+}
+
+#[cfg(feature = "tui")]
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            border: Border::Plain,
+            name: Alignment::Center,
+            subtitle: Alignment::Center,
+            hint: Alignment::Right,
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Border {
+    #[default]
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+    /// No border, the titles are still drawn on the top row.
+    None,
+}
+
+/// What is stripped from the captured lines. The escape sequences other than SGR, e.g. moving
+/// the cursor or erasing, are always stripped.
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CleanMode {
+    /// Keep the SGR color codes, unless rendered with `ansi_colors`.
+    Off,
+    /// Strip the SGR color codes.
+    #[default]
+    Ansi,
+    /// Strip the SGR color codes and the matches of the pattern, e.g. a timestamp the process
+    /// prepends. An invalid pattern fails when the process is added.
+    Custom(String),
 }
 
+/// Timestamp prepended to each line when it's captured.
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Timestamps {
+    #[default]
+    None,
+    /// UTC wall clock time, e.g. `[12:00:03.123]`.
+    Time,
+    /// Time elapsed since the process was spawned (or the terminal started, for the Main
+    /// section), e.g. `[+3.123s]`.
+    #[cfg_attr(feature = "serde", serde(rename = "elapsed"))]
+    ElapsedSinceStart,
+}
+
+/// Severity of a message of the Main section, printed with `tdebug!`, `tinfo!`, `twarn!` or
+/// `terror!`, and shown in dark gray, gray, yellow or red.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Level {
+    #[default]
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[cfg(feature = "tui")]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum ScrollSettings {
     Disable,
-    Enable { up: KeyCode, down: KeyCode },
+    Enable {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::key_code"))]
+        up: KeyCode,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::key_code"))]
+        down: KeyCode,
+    },
 }
 
+#[cfg(feature = "tui")]
 impl ScrollSettings {
     pub fn enable(up: KeyCode, down: KeyCode) -> Self {
         ScrollSettings::Enable { up, down }
     }
 }
+
+/// Action of the terminal whose key can be replaced with `TerminalSettings::key_bindings`.
+#[cfg(feature = "tui")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum KeyAction {
+    /// `/`
+    Filter,
+    /// Tab
+    NextPane,
+    /// Shift+Tab
+    PreviousPane,
+    /// `]`
+    NextGroup,
+    /// `[`
+    PreviousGroup,
+    /// `m`
+    Merged,
+    /// `s`
+    Source,
+    /// `y`
+    Copy,
+    /// `f`
+    Follow,
+    /// `w`
+    Wrap,
+    /// Ctrl+L
+    Clear,
+    /// Home
+    Top,
+    /// End
+    Bottom,
+    /// Shift+End
+    BottomAll,
+    /// PageUp
+    PageUp,
+    /// PageDown
+    PageDown,
+    /// `n`
+    NextError,
+    /// Shift+N
+    PreviousError,
+}
+
+/// Names of the keys without a character, as read by `parse_key`.
+#[cfg(feature = "tui")]
+const KEY_NAMES: [(&str, KeyCode); 17] = [
+    ("Backspace", KeyCode::Backspace),
+    ("Enter", KeyCode::Enter),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Esc", KeyCode::Esc),
+    ("Space", KeyCode::Char(' ')),
+    ("Null", KeyCode::Null),
+];
+
+/// Key named `name`, e.g. `"Left"`, `"F1"` or `"a"`, to read the keys of the settings from a
+/// configuration file. The names are case insensitive, but the characters aren't.
+#[cfg(feature = "tui")]
+pub fn parse_key(name: &str) -> anyhow::Result<KeyCode> {
+    let mut chars = name.chars();
+
+    if let (Some(char), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(char));
+    }
+
+    if let Some((_, code)) = KEY_NAMES
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    {
+        return Ok(*code);
+    }
+
+    name.strip_prefix(['F', 'f'])
+        .and_then(|number| number.parse().ok())
+        .filter(|number| (1..=24).contains(number))
+        .map(KeyCode::F)
+        .ok_or_else(|| anyhow::anyhow!("Unknown key '{name}'."))
+}
+
+/// Name of `code` read back by `parse_key`, `None` for the keys it doesn't name.
+#[cfg(feature = "tui")]
+pub fn key_name(code: KeyCode) -> Option<String> {
+    match code {
+        KeyCode::Char(' ') => Some("Space".to_string()),
+        KeyCode::Char(char) => Some(char.to_string()),
+        KeyCode::F(number) => Some(format!("F{number}")),
+        code => KEY_NAMES
+            .iter()
+            .find(|(_, key)| *key == code)
+            .map(|(name, _)| name.to_string()),
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct TerminalSettings {
+    /// Enter raw mode and the alternate screen when the terminal starts, and restore them on exit.
+    /// Disable it when the caller already manages the screen, e.g. when embedded in another TUI.
+    pub manage_terminal: bool,
+    /// Capture the mouse, to scroll the pane under the cursor with the wheel and put a pane in
+    /// full screen with a click. Text can still be selected holding Shift, in most terminals.
+    /// Only applies with `manage_terminal`.
+    pub mouse: bool,
+    /// Show in the Main section what the program prints to its stdout, e.g. with `println!`,
+    /// instead of letting it corrupt the screen. Stdout is restored once the terminal closes.
+    /// Only applies with `manage_terminal`, on Unix.
+    pub capture_stdout: bool,
+    /// Maximum amount of lines kept in the Main section, `None` for unbounded.
+    pub max_main_lines: Option<usize>,
+    pub main_timestamps: Timestamps,
+    /// Minimum interval between two redraws of the terminal, which is only redrawn when what it
+    /// shows changes.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "refresh_ms", with = "crate::serialize::millis")
+    )]
+    pub refresh: Duration,
+    /// Interval between two checks of the loops waiting on a process, like the searches or the
+    /// exit watchers.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "poll_interval_ms", with = "crate::serialize::millis")
+    )]
+    pub poll_interval: Duration,
+    /// How the process panes are arranged next to the Main section.
+    pub layout: PaneLayout,
+    /// File the messages of the Main section are appended to.
+    pub main_log_to: Option<PathBuf>,
+    /// Give a distinct color to the panes of each process without one in its settings.
+    pub auto_colors: bool,
+    /// Show in the title of each process pane its amount of lines, and whether the process is
+    /// running (`●`), exited successfully (`✓`) or failed (`✗`).
+    pub pane_status: bool,
+    /// Show in the title of each process pane the lines per second and the bytes captured
+    /// from the process, to spot the ones flooding the terminal.
+    pub pane_stats: bool,
+    /// Draw a scrollbar on the right border of the panes whose lines don't fit.
+    pub scrollbar: bool,
+    /// Borders of the panes and placement of their titles.
+    #[cfg(feature = "tui")]
+    pub theme: Theme,
+    /// Reserve the bottom row for a bar showing the amount of processes, the focused pane and
+    /// the message set with `set_status`.
+    pub status_bar: bool,
+    /// Exit the whole process once the terminal is closed with Ctrl+C or `end_terminal`,
+    /// instead of returning the control to the caller.
+    pub exit_on_close: bool,
+    /// Leave the children of the shown processes running once the terminal is closed, instead of
+    /// terminating them, e.g. for daemonized services.
+    pub keep_children_alive: bool,
+    /// Print the lines of a terminal created with `new_instance` to stdout, the ones of the
+    /// processes prefixed with their name. The global terminal does it in headless mode only.
+    pub echo: bool,
+    /// Key closing the terminal, pressed along with `quit_modifiers`. Ctrl+C by default.
+    #[cfg(feature = "tui")]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::key_code"))]
+    pub quit_key: KeyCode,
+    #[cfg(feature = "tui")]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::modifiers"))]
+    pub quit_modifiers: KeyModifiers,
+    /// Keys replacing the default one of an action, an action listed several times being bound
+    /// to each of its keys.
+    #[cfg(feature = "tui")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "keys", with = "crate::serialize::key_bindings")
+    )]
+    pub key_bindings: Vec<(KeyAction, KeyCode, KeyModifiers)>,
+    /// Settings the processes start from with `process_settings`, e.g. the `[process]` table of
+    /// the configuration file.
+    #[cfg_attr(feature = "serde", serde(rename = "process"))]
+    pub process_defaults: ProcessSettings,
+    /// Messages of the Main section below this level are dropped.
+    pub min_level: Level,
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            manage_terminal: true,
+            mouse: true,
+            capture_stdout: false,
+            max_main_lines: Some(DEFAULT_MAX_LINES),
+            main_timestamps: Timestamps::None,
+            refresh: DEFAULT_REFRESH,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            layout: PaneLayout::Horizontal,
+            main_log_to: None,
+            auto_colors: false,
+            pane_status: false,
+            pane_stats: false,
+            scrollbar: false,
+            #[cfg(feature = "tui")]
+            theme: Theme::default(),
+            status_bar: false,
+            exit_on_close: false,
+            keep_children_alive: false,
+            echo: false,
+            #[cfg(feature = "tui")]
+            quit_key: KeyCode::Char('c'),
+            #[cfg(feature = "tui")]
+            quit_modifiers: KeyModifiers::CONTROL,
+            #[cfg(feature = "tui")]
+            key_bindings: vec![],
+            process_defaults: ProcessSettings::builder().build(),
+            min_level: Level::Debug,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_round_trip() {
+        for code in [
+            KeyCode::Left,
+            KeyCode::F(1),
+            KeyCode::Char('a'),
+            KeyCode::Char(' '),
+        ] {
+            assert_eq!(parse_key(&key_name(code).unwrap()).unwrap(), code);
+        }
+
+        assert_eq!(parse_key("pageup").unwrap(), KeyCode::PageUp);
+        assert!(parse_key("F25").is_err());
+        assert!(parse_key("Nope").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn settings_round_trip_through_serde() {
+        let settings = TerminalSettings {
+            refresh: Duration::from_millis(100),
+            layout: PaneLayout::Grid { cols: 3 },
+            quit_key: KeyCode::Char('q'),
+            key_bindings: vec![
+                (KeyAction::NextError, KeyCode::Down, KeyModifiers::ALT),
+                (
+                    KeyAction::NextError,
+                    KeyCode::Char('n'),
+                    KeyModifiers::empty(),
+                ),
+                (
+                    KeyAction::PreviousError,
+                    KeyCode::Char('E'),
+                    KeyModifiers::SHIFT,
+                ),
+            ],
+            process_defaults: ProcessSettings::builder()
+                .scroll(ScrollSettings::enable(KeyCode::Left, KeyCode::F(1)))
+                .on_exit(OnExit::Remove(Duration::from_secs(5)))
+                .focus_key(KeyCode::Char(' '))
+                .color(Color::Cyan)
+                .build(),
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&settings).unwrap();
+
+        assert_eq!(value["refresh_ms"], 100);
+        assert_eq!(
+            value["layout"],
+            serde_json::json!({ "grid": { "cols": 3 } })
+        );
+        assert_eq!(value["quit_modifiers"], "Ctrl");
+        assert_eq!(
+            value["keys"]["next_error"],
+            serde_json::json!(["Alt+Down", "n"])
+        );
+        assert_eq!(value["keys"]["previous_error"], serde_json::json!(["E"]));
+        assert_eq!(
+            value["process"]["scroll"],
+            serde_json::json!({ "enable": { "up": "Left", "down": "F1" } })
+        );
+        assert_eq!(
+            value["process"]["on_exit"],
+            serde_json::json!({ "remove_after_ms": 5000 })
+        );
+        assert_eq!(value["process"]["focus_key"], "Space");
+
+        assert!(serde_json::from_value::<TerminalSettings>(value).unwrap() == settings);
+
+        let process: ProcessSettings =
+            serde_json::from_str(r#"{ "on_exit": "remove", "focus_key": "f2" }"#).unwrap();
+
+        assert!(process.on_exit == OnExit::Remove(Duration::ZERO));
+        assert_eq!(process.focus_key, Some(KeyCode::F(2)));
+
+        let Err(err) = serde_json::from_str::<ProcessSettings>(r#"{ "focus_key": "F25" }"#) else {
+            panic!("The key is unknown.");
+        };
+
+        assert!(err.to_string().starts_with("Unknown key 'F25'."));
+    }
+}
@@ -1,7 +1,13 @@
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "tui")]
+use std::sync::TryLockError;
 
 /// A wrapper over the `Arc<RwLock<T>>` smart pointer, providing some convenience
 /// methods.
+///
+/// A lock poisoned by a thread panicking while holding it is recovered, so a single failing
+/// thread doesn't bring down every other one sharing the value.
 #[derive(Debug, Default)]
 pub struct Shared<S> {
     inner: Arc<RwLock<S>>,
@@ -14,16 +20,37 @@ impl<S> Shared<S> {
         }
     }
 
-    pub fn read_access(&self) -> RwLockReadGuard<S> {
-        self.inner
-            .read()
-            .unwrap_or_else(|err| panic!("poisoned lock: {err:?}"))
+    /// Whether both point to the same value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    pub fn read_access(&self) -> RwLockReadGuard<'_, S> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn write_access(&self) -> RwLockWriteGuard<'_, S> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Like `read_access`, but `None` instead of blocking if the lock is held for writing.
+    #[cfg(feature = "tui")]
+    pub fn try_read_access(&self) -> Option<RwLockReadGuard<'_, S>> {
+        match self.inner.try_read() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
     }
 
-    pub fn write_access(&self) -> RwLockWriteGuard<S> {
-        self.inner
-            .write()
-            .unwrap_or_else(|err| panic!("poisoned lock: {err:?}"))
+    /// Like `write_access`, but `None` instead of blocking if the lock is held.
+    #[cfg(feature = "tui")]
+    pub fn try_write_access(&self) -> Option<RwLockWriteGuard<'_, S>> {
+        match self.inner.try_write() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
     }
 
     pub fn read_with<F, T>(&self, action: F) -> T
@@ -39,6 +66,22 @@ impl<S> Shared<S> {
     {
         action(self.write_access())
     }
+
+    #[cfg(feature = "tui")]
+    pub fn try_read_with<F, T>(&self, action: F) -> Option<T>
+    where
+        F: FnOnce(RwLockReadGuard<S>) -> T,
+    {
+        self.try_read_access().map(action)
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn try_write_with<F, T>(&self, action: F) -> Option<T>
+    where
+        F: FnOnce(RwLockWriteGuard<S>) -> T,
+    {
+        self.try_write_access().map(action)
+    }
 }
 
 impl<S> Clone for Shared<S> {
@@ -48,3 +91,25 @@ impl<S> Clone for Shared<S> {
         }
     }
 }
+
+#[cfg(all(test, feature = "tui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_access_gives_up_on_a_held_lock() {
+        let shared = Shared::new(0);
+
+        {
+            let _write = shared.write_access();
+            assert!(shared.try_read_with(|value| *value).is_none());
+        }
+
+        let read = shared.read_access();
+        assert!(shared.try_write_with(|mut value| *value = 1).is_none());
+        drop(read);
+
+        assert_eq!(shared.try_write_with(|mut value| *value = 2), Some(()));
+        assert_eq!(shared.try_read_with(|value| *value), Some(2));
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    time::Duration,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Stage {
+    Open,
+    /// Close requested, the draw and input threads are stopping.
+    Closing,
+    /// The draw thread stopped and restored the screen.
+    #[cfg(feature = "tui")]
+    Restored,
+    /// The exit callback ran, control is back to the caller.
+    Closed,
+}
+
+/// Progress of the closing of a terminal, shared by the threads it stops through its clones.
+#[derive(Clone)]
+pub(crate) struct Shutdown(Arc<ShutdownState>);
+
+struct ShutdownState {
+    stage: Mutex<Stage>,
+    condvar: Condvar,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self(Arc::new(ShutdownState {
+            stage: Mutex::new(Stage::Open),
+            condvar: Condvar::new(),
+        }))
+    }
+}
+
+impl Shutdown {
+    pub fn stage(&self) -> Stage {
+        *self.0.stage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Move forward to `stage`, returning false if it was already reached.
+    pub fn advance(&self, stage: Stage) -> bool {
+        let mut current = self.0.stage.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if *current >= stage {
+            return false;
+        }
+
+        *current = stage;
+        self.0.condvar.notify_all();
+
+        true
+    }
+
+    /// Block until `stage` is reached, or `timeout` expires, returning whether it was reached.
+    pub fn wait(&self, stage: Stage, timeout: Option<Duration>) -> bool {
+        let current = self.0.stage.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let current = match timeout {
+            Some(timeout) => {
+                self.0
+                    .condvar
+                    .wait_timeout_while(current, timeout, |current| *current < stage)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0
+            }
+            None => self
+                .0
+                .condvar
+                .wait_while(current, |current| *current < stage)
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+
+        *current >= stage
+    }
+}
@@ -0,0 +1,129 @@
+use crate::shared::Shared;
+
+/// Area of each pane on the screen, along with its focus index, to find the pane under the mouse.
+#[cfg(feature = "tui")]
+pub type PaneAreas = Vec<(ratatui::layout::Rect, usize)>;
+
+/// Nothing is drawn without the `tui` feature, so there's no pane to find.
+#[cfg(not(feature = "tui"))]
+pub type PaneAreas = ();
+
+/// Scroll of a pane, read with `get_scroll` and written with `set_scroll`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScrollStatus {
+    /// Columns the clipped lines are scrolled by.
+    pub x: u16,
+    /// Absolute position of the messages the view is anchored to, `None` when following the tail.
+    pub y: Option<usize>,
+    /// Whether the long lines are wrapped, otherwise they are clipped and scrolled with `x`.
+    pub wrap: bool,
+}
+
+impl Default for ScrollStatus {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl ScrollStatus {
+    pub fn new(wrap: bool) -> Self {
+        Self {
+            x: 0,
+            y: None,
+            wrap,
+        }
+    }
+}
+
+/// Filter typed with `/` on the pane in full screen.
+#[derive(Default, Clone, PartialEq)]
+pub struct FilterStatus {
+    /// Focus index of the filtered pane.
+    pub focus: usize,
+    pub text: String,
+    /// Whether the typed characters are still routed to the filter.
+    pub editing: bool,
+}
+
+/// Key bindings listed by the help popup, as the keys along with what they do.
+pub type HelpLines = Vec<(String, String)>;
+
+#[cfg(feature = "tui")]
+pub type DetachBaseStatus = BaseStatus<
+    ScrollStatus,
+    Option<usize>,
+    Option<FilterStatus>,
+    (),
+    Option<HelpLines>,
+    Option<String>,
+    Option<String>,
+    bool,
+    Option<String>,
+>;
+
+#[derive(Default, Clone, PartialEq)]
+pub struct BaseStatus<
+    MS = Shared<ScrollStatus>,
+    F = Shared<Option<usize>>,
+    FI = Shared<Option<FilterStatus>>,
+    A = Shared<PaneAreas>,
+    H = Shared<Option<HelpLines>>,
+    ST = Shared<Option<String>>,
+    G = Shared<Option<String>>,
+    M = Shared<bool>,
+    SO = Shared<Option<String>>,
+> {
+    pub main_scroll: MS,
+    pub focus: F,
+    pub filter: FI,
+    /// Panes of the last frame drawn, written by the draw thread.
+    pub areas: A,
+    /// Bindings shown by the help popup, `None` while it's closed.
+    pub help: H,
+    /// Message shown by the status bar, set with `set_status`.
+    pub status_text: ST,
+    /// Group of the tab shown, the first one when `None` or once its processes are removed.
+    pub group: G,
+    /// Whether the Main section shows the merged timeline of the processes instead of its
+    /// messages, toggled with `m`.
+    pub merged: M,
+    pub merged_scroll: MS,
+    /// Source the Main section shows the messages of alone, cycled with `s`.
+    pub source: SO,
+}
+
+impl BaseStatus {
+    /// Update the current focus after `count` panes starting at index `first` are removed,
+    /// falling back to the main view if one of them was in full screen.
+    pub fn remove_focus_panes(&self, first: usize, count: usize) {
+        // The indexes of the panes change, so the filter could end up on another one.
+        self.filter.write_with(|mut filter| *filter = None);
+
+        self.focus.write_with(|mut focus| match *focus {
+            Some(index) if index >= first + count => *focus = Some(index - count),
+            Some(index) if index >= first => *focus = None,
+            _ => {}
+        });
+    }
+
+    /// Snapshot of the status, `None` if any of the values is being written.
+    #[cfg(feature = "tui")]
+    pub fn try_detach(&self) -> Option<DetachBaseStatus> {
+        Some(BaseStatus {
+            main_scroll: self
+                .main_scroll
+                .try_read_with(|main_scroll| main_scroll.clone())?,
+            focus: *self.focus.try_read_access()?,
+            filter: self.filter.try_read_with(|filter| filter.clone())?,
+            areas: (),
+            help: self.help.try_read_with(|help| help.clone())?,
+            status_text: self.status_text.try_read_with(|text| text.clone())?,
+            group: self.group.try_read_with(|group| group.clone())?,
+            merged: *self.merged.try_read_access()?,
+            merged_scroll: self
+                .merged_scroll
+                .try_read_with(|merged_scroll| merged_scroll.clone())?,
+            source: self.source.try_read_with(|source| source.clone())?,
+        })
+    }
+}
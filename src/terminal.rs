@@ -1,44 +1,210 @@
 use {
     crate::{
-        keyboard_actions::{
-            Action, ActionScroll, ActionType, BaseStatus, DetachBaseStatus, KeyBoardActions,
-            KeyCodeExt, ScrollStatus,
+        capture::{
+            capture, restart_capture, spawn_capture, thread_channel, thread_error, thread_output,
+            CaptureTask,
         },
+        counters::Counters,
+        events::{Events, TerminalEvent},
+        messages::{LogFile, Message, Messages},
+        redraw::Redraw,
         shared::Shared,
-        MessageSettings, ProcessSettings, ScrollSettings,
+        shutdown::{Shutdown, Stage},
+        status::{BaseStatus, ScrollStatus},
+        Level, MessageSettings, OnExit, ProcessSettings, ProcessSettingsBuilder, RestartPolicy,
+        TerminalSettings,
     },
     anyhow::{anyhow, Result},
-    crossterm::event::KeyModifiers,
-    ratatui::{
-        layout::{Constraint, Direction, Layout, Rect},
-        style::{Style, Stylize},
-        text::{Line, Text},
-        widgets::{Block, Borders, List, ListState},
-        Frame,
+    std::{
+        io::{self, stdout, IsTerminal, Read, Write},
+        process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc::{channel, Receiver, Sender},
+            LazyLock, OnceLock,
+        },
+        thread::{sleep, JoinHandle},
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+#[cfg(feature = "tui")]
+use {
+    crate::{
+        capture::ANSI_REGEX,
+        counters::Stats,
+        keyboard_actions::{
+            Action, ActionPane, ActionScroll, ActionType, KeyBoardActions, KeyCodeExt,
+        },
+        render::{draw_frame, render_snapshot, DrawCache, DrawOptions, Snapshots, PALETTE},
+        screen::{capture_stdout, restore_stdout, Screen},
+        ScrollSettings,
     },
+    anyhow::bail,
+    crossterm::{
+        event::{DisableMouseCapture, EnableMouseCapture, KeyCode, KeyModifiers},
+        terminal::EnterAlternateScreen,
+    },
+    ratatui::{backend::CrosstermBackend, style::Color, text::Line},
     std::{
+        fs::File,
         io::{BufRead, BufReader},
-        process::{Child, ChildStderr, ChildStdout},
-        sync::LazyLock,
-        thread::sleep,
-        time::Duration,
+        sync::Arc,
     },
 };
 
+#[cfg(feature = "tokio")]
+use crate::capture::spawn_async_capture;
+
 pub static TERMINAL: LazyLock<Terminal> = LazyLock::new(Terminal::new);
 
-pub(crate) type SharedMessages = Shared<Vec<String>>;
-type SharedProcesses = Shared<Vec<Process>>;
-type DetachProcess = Process<Vec<String>, Vec<String>, ScrollStatus, ()>;
-type DrawCacheDetach = DrawCache<Vec<String>, DetachBaseStatus, Vec<DetachProcess>>;
+static SETTINGS: OnceLock<TerminalSettings> = OnceLock::new();
+
+/// Whether `TERMINAL` was created, kept apart so checking it doesn't create it.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the draw thread of `TERMINAL` is up, from its spawn until it restores the screen.
+static DRAWING: AtomicBool = AtomicBool::new(false);
+
+/// Longest time the terminal isn't redrawn, in case a change didn't request it.
+#[cfg(feature = "tui")]
+const REDRAW_FALLBACK: Duration = Duration::from_secs(1);
+
+/// Environment variable forcing the headless mode when set to `1`.
+const DISABLE_ENV: &str = "PROCESS_TERMINAL_DISABLE";
+
+/// Whether the terminal isn't interactive, e.g. in CI or piped, so the lines are printed to
+/// stdout, prefixed with the process name, instead of being drawn.
+/// Always the case without the `tui` feature, which compiles the draw and input threads.
+static HEADLESS: LazyLock<bool> = LazyLock::new(|| {
+    !cfg!(feature = "tui")
+        || std::env::var(DISABLE_ENV).is_ok_and(|value| value == "1")
+        || !stdout().is_terminal()
+});
+
+/// How long closing the terminal waits for the draw thread to restore the screen.
+#[cfg(feature = "tui")]
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long closing the terminal waits for its threads to stop, e.g. capturing the output of a
+/// child kept alive.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long the children get to exit after being asked to terminate, before being killed.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// Longest delay between two restarts of a process by its `RestartPolicy`.
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+pub(crate) type SharedMessages = Shared<Messages>;
+pub(crate) type SharedProcesses = Shared<Vec<Process>>;
+#[cfg(feature = "tui")]
+pub(crate) type DetachProcess = Process<
+    Arc<Messages>,
+    Arc<Messages>,
+    ScrollStatus,
+    (),
+    Decorations,
+    (),
+    Stats,
+    (),
+    (),
+    (),
+    Option<ExitStatus>,
+    (),
+>;
+pub(crate) type Subscribers = Vec<Sender<String>>;
+/// Searches pending on a process, each satisfied independently.
+pub(crate) type Searches = Vec<SearchMessage>;
 pub(crate) type ExitCallback = Option<Box<dyn Fn() + Send + Sync>>;
+pub(crate) type LineTap = Option<Box<dyn Fn(&str, Stream, &str) + Send + Sync>>;
+
+/// Where the captured lines go besides the buffers of their process.
+#[derive(Clone, Default)]
+pub(crate) struct Taps {
+    pub line_tap: Shared<LineTap>,
+    /// Lines of every process in arrival order, shown by the merged timeline.
+    pub merged: SharedMessages,
+}
+
+/// What a terminal shares with its threads, key bindings and process handles to be redrawn
+/// and closed, independently from the other terminals.
+#[derive(Clone)]
+pub(crate) struct Control {
+    exit_callback: Shared<ExitCallback>,
+    pub processes: SharedProcesses,
+    pub shutdown: Shutdown,
+    pub redraw: Redraw,
+    /// Whether the terminal draws on the screen, which is restored once it's closed.
+    screen: bool,
+    #[cfg(feature = "tui")]
+    manage_terminal: bool,
+    exit_on_close: bool,
+    keep_children_alive: bool,
+    poll_interval: Duration,
+}
+
+impl Control {
+    pub fn new(processes: SharedProcesses, screen: bool, settings: &TerminalSettings) -> Self {
+        Self {
+            exit_callback: Default::default(),
+            processes,
+            shutdown: Default::default(),
+            redraw: Default::default(),
+            screen,
+            #[cfg(feature = "tui")]
+            manage_terminal: settings.manage_terminal,
+            exit_on_close: settings.exit_on_close,
+            keep_children_alive: settings.keep_children_alive,
+            poll_interval: settings.poll_interval,
+        }
+    }
+
+    /// Stop drawing, restore the screen, stop the children and run the exit callback, once.
+    /// The process is only exited by the global terminal with `TerminalSettings::exit_on_close`,
+    /// otherwise the control is left to the caller, waiting on `wait_terminal_closed`.
+    pub fn close(&self) {
+        if !self.shutdown.advance(Stage::Closing) {
+            return;
+        }
+
+        // The draw thread restores the screen between two frames, unless it's not running.
+        // In headless mode, the screen was never taken over.
+        #[cfg(feature = "tui")]
+        if self.screen && Terminal::is_running() {
+            self.redraw.request();
+
+            if !self.shutdown.wait(Stage::Restored, Some(CLOSE_TIMEOUT)) && self.manage_terminal {
+                restore_screen();
+            }
+        }
+
+        if !self.keep_children_alive {
+            stop_children(&self.processes, self.poll_interval);
+        }
+
+        if let Some(callback) = self.exit_callback.read_access().as_ref() {
+            callback();
+        }
+
+        self.shutdown.advance(Stage::Closed);
+
+        if self.screen && self.exit_on_close {
+            std::process::exit(0);
+        }
+    }
+}
+#[cfg(feature = "tui")]
+pub(crate) type KeyHandler = Arc<dyn Fn() + Send + Sync>;
 
 macro_rules! spawn_thread {
     ($callback:expr) => {
-        std::thread::spawn(move || $callback);
+        std::thread::spawn(move || $callback)
     };
 }
 
+pub(crate) use spawn_thread;
+
 macro_rules! let_clone {
     ($init:expr, $( $name:ident | $($clone:ident)|* : $ty:ty),*) => {
         $(
@@ -50,738 +216,3871 @@ macro_rules! let_clone {
     };
 }
 
+pub(crate) use let_clone;
+
+/// Terminal showing the Main section next to the panes of the processes.
+///
+/// The global one, `TERMINAL`, is driven by the free functions and draws on the screen. Others,
+/// created with `new_instance`, only keep their own state, drawn with `render_to_string`.
 pub struct Terminal {
     processes: SharedProcesses,
     main_messages: SharedMessages,
+    main_scroll: Shared<ScrollStatus>,
+    status: BaseStatus,
+    #[cfg(feature = "tui")]
     inputs: Shared<KeyBoardActions>,
-    exit_callback: Shared<ExitCallback>,
+    control: Control,
+    taps: Taps,
+    events: Events,
+    /// Draw and input threads, joined once the terminal is closed.
+    threads: Shared<Vec<JoinHandle<()>>>,
+    settings: TerminalSettings,
+    /// Whether the lines are printed to stdout, prefixed with the process name.
+    echo: bool,
 }
 
 impl Terminal {
     fn new() -> Terminal {
+        let terminal = Self::build(SETTINGS.get_or_init(Default::default).clone(), true);
+
+        INITIALIZED.store(true, Ordering::Release);
+
+        terminal
+    }
+
+    pub(crate) fn is_initialized() -> bool {
+        INITIALIZED.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_running() -> bool {
+        DRAWING.load(Ordering::Acquire)
+    }
+
+    /// Terminal independent from the global one and from the screen, e.g. to test what it
+    /// shows with `render_to_string`. Its processes are only drawn through its methods, and
+    /// printed to stdout with `TerminalSettings::echo`. Dropping it stops its children, unless
+    /// `TerminalSettings::keep_children_alive` is set.
+    pub fn new_instance(settings: TerminalSettings) -> Result<Terminal> {
+        let log = settings
+            .main_log_to
+            .as_deref()
+            .map(LogFile::open)
+            .transpose()?;
+
+        let terminal = Self::build(settings, false);
+
+        if let Some(log) = log {
+            terminal
+                .main_messages
+                .write_with(|mut messages| messages.set_log(log));
+        }
+
+        Ok(terminal)
+    }
+
+    // Nothing is drawn on the screen without the `tui` feature.
+    #[cfg_attr(not(feature = "tui"), allow(unused_variables))]
+    fn build(settings: TerminalSettings, screen: bool) -> Terminal {
+        // Instances only print their lines if asked to.
+        let echo = if screen { *HEADLESS } else { settings.echo };
+
         let_clone!(
             Default::default(),
-            main_messages | _main_messages: SharedMessages,
-            processes     | _processes:     SharedProcesses
+            processes | _processes: SharedProcesses
         );
 
-        let (inputs, scroll_status, exit_callback) = KeyBoardActions::new(main_messages.clone());
+        let control = Control::new(processes.clone(), screen, &settings);
+
+        let mut messages = Messages::new(settings.max_main_lines, settings.main_timestamps);
+        messages.set_redraw(control.redraw.clone());
+
+        if echo {
+            messages.set_echo(String::new());
+        }
 
         let_clone!(
-            Shared::new(inputs),
-            inputs | _inputs: Shared<KeyBoardActions>
+            Shared::new(messages),
+            main_messages | _main_messages: SharedMessages
         );
 
-        #[cfg(test)]
-        let not_in_test = false;
-        #[cfg(not(test))]
-        let not_in_test = true;
+        // The merged timeline is capped like the Main section.
+        let mut merged = Messages::new(settings.max_main_lines, settings.main_timestamps);
+        merged.set_redraw(control.redraw.clone());
 
-        if std::env::args().any(|arg| arg.starts_with("--exact")) || not_in_test {
-            spawn_thread!(thread_draw(_main_messages, scroll_status, _processes));
-        }
+        let taps = Taps {
+            line_tap: Default::default(),
+            merged: Shared::new(merged),
+        };
+
+        #[cfg(feature = "tui")]
+        let (inputs, status) = {
+            let (inputs, status) = KeyBoardActions::new(
+                main_messages.clone(),
+                taps.merged.clone(),
+                control.clone(),
+                &settings,
+            );
+
+            (Shared::new(inputs), status)
+        };
+
+        // There are no keys to bind.
+        #[cfg(not(feature = "tui"))]
+        let status: BaseStatus = Default::default();
 
-        spawn_thread!(thread_input(_inputs));
+        let main_scroll = status.main_scroll.clone();
+
+        #[cfg_attr(not(feature = "tui"), allow(unused_mut))]
+        let mut threads = vec![];
+        let events = Events::default();
+
+        #[cfg(feature = "tui")]
+        {
+            #[cfg(test)]
+            let not_in_test = false;
+            #[cfg(not(test))]
+            let not_in_test = true;
+
+            if screen
+                && !*HEADLESS
+                && (std::env::args().any(|arg| arg.starts_with("--exact")) || not_in_test)
+            {
+                if settings.manage_terminal {
+                    set_panic_hook(control.shutdown.clone());
+                }
+
+                if settings.manage_terminal && settings.capture_stdout {
+                    match capture_stdout() {
+                        Ok(pipe) => {
+                            let _main_messages = main_messages.clone();
+
+                            spawn_thread!(thread_stdout(pipe, _main_messages));
+                        }
+                        Err(err) => main_messages.write_with(|mut messages| {
+                            messages.push(Message::new(
+                                format!("Failed to capture stdout: {err}"),
+                                Stream::Err,
+                            ));
+                        }),
+                    }
+                }
+
+                let manage_terminal = settings.manage_terminal;
+                let mouse = settings.mouse;
+                let refresh = settings.refresh;
+                let options = DrawOptions::new(&settings);
+
+                DRAWING.store(true, Ordering::Release);
+
+                let merged_messages = taps.merged.clone();
+                let _status = status.clone();
+                let _events = events.clone();
+                let _control = control.clone();
+
+                threads.push(spawn_thread!(thread_draw(
+                    _main_messages,
+                    merged_messages,
+                    _status,
+                    _processes,
+                    _events,
+                    _control,
+                    manage_terminal,
+                    mouse,
+                    refresh,
+                    options
+                )));
+            }
+
+            let poll_interval = settings.poll_interval;
+
+            if screen && !*HEADLESS {
+                let _inputs = inputs.clone();
+                let _processes = processes.clone();
+                let focus = status.focus.clone();
+                let _events = events.clone();
+                let _control = control.clone();
+
+                threads.push(spawn_thread!(thread_input(
+                    _inputs,
+                    _processes,
+                    focus,
+                    _events,
+                    _control,
+                    poll_interval
+                )));
+            }
+        }
 
         Terminal {
             processes,
             main_messages,
+            main_scroll,
+            status,
+            #[cfg(feature = "tui")]
             inputs,
-            exit_callback,
+            control,
+            taps,
+            events,
+            threads: Shared::new(threads),
+            settings,
+            echo,
         }
     }
 
-    pub(crate) fn add_process(
+    pub(crate) fn init(settings: TerminalSettings) -> Result<()> {
+        let log = settings
+            .main_log_to
+            .as_deref()
+            .map(LogFile::open)
+            .transpose()?;
+
+        SETTINGS
+            .set(settings)
+            .map_err(|_| anyhow!("Terminal already initialized."))?;
+
+        LazyLock::force(&TERMINAL);
+
+        if let Some(log) = log {
+            TERMINAL
+                .main_messages
+                .write_with(|mut messages| messages.set_log(log));
+        }
+
+        Ok(())
+    }
+
+    /// Builder starting from the default process settings of the terminal.
+    pub fn process_settings(&self) -> ProcessSettingsBuilder {
+        self.settings.process_defaults.clone().into_builder()
+    }
+
+    pub fn add_process(
         &self,
         name: &str,
         mut child: Child,
         settings: ProcessSettings,
-    ) -> Result<()> {
-        let process = Process::new(name.to_string(), settings);
-
-        let pre_count = self.processes.write_with(|mut processes| {
-            let pre_count = processes.iter().fold(0, |buff, process| {
-                let count = match &process.settings.messages {
-                    MessageSettings::Output | MessageSettings::Error => 1,
-                    MessageSettings::All => 2,
-                    MessageSettings::None => 0,
-                };
+    ) -> Result<ProcessHandle> {
+        if self.find_process(name).is_ok() {
+            return Err(anyhow!("Process '{name}' already exists."));
+        }
 
-                buff + count
-            });
+        let process = self.new_process(name, settings);
 
-            processes.push(process.clone());
-            pre_count
-        });
+        spawn_capture(&process, &mut child, &self.taps)?;
 
-        let focus_indexes =
-            match &process.settings.messages {
-                MessageSettings::Output => {
-                    let stdout = child.stdout.take().ok_or_else(|| {
-                        anyhow::anyhow!("Failed to get stdout on process: {name}")
-                    })?;
+        process.set_spawned(child.id());
 
-                    let _out_messages = process.out_messages.clone();
+        process.stdin.write_with(|mut stdin| {
+            *stdin = child.stdin.take();
+        });
 
-                    spawn_thread!(thread_output(
-                        stdout,
-                        _out_messages,
-                        process.search_message,
-                        process.settings.clear_regex
-                    ));
+        process.child.write_with(|mut slot| {
+            *slot = Some(child);
+        });
 
-                    vec![pre_count + 1]
-                }
-                MessageSettings::Error => {
-                    let stderr = child.stderr.take().ok_or_else(|| {
-                        anyhow::anyhow!("Failed to get stderr on process: {name}")
-                    })?;
+        self.attach(process.clone())?;
 
-                    let _err_messages = process.err_messages.clone();
+        Ok(ProcessHandle {
+            process,
+            registry: self.registry(),
+        })
+    }
 
-                    spawn_thread!(thread_error(
-                        stderr,
-                        _err_messages,
-                        process.settings.clear_regex
-                    ));
+    #[cfg(feature = "tokio")]
+    pub fn add_process_async(
+        &self,
+        name: &str,
+        mut child: tokio::process::Child,
+        settings: ProcessSettings,
+    ) -> Result<ProcessHandle> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|err| anyhow!("Process '{name}' must be added from a tokio runtime: {err}"))?;
 
-                    vec![pre_count + 1]
-                }
-                MessageSettings::All => {
-                    let stdout = child.stdout.take().ok_or_else(|| {
-                        anyhow::anyhow!("Failed to get stdout on process: {name}")
-                    })?;
-
-                    let stderr = child.stderr.take().ok_or_else(|| {
-                        anyhow::anyhow!("Failed to get stderr on process: {name}")
-                    })?;
-
-                    let _out_messages = process.out_messages.clone();
-                    let _err_messages = process.err_messages.clone();
-
-                    spawn_thread!(thread_output(
-                        stdout,
-                        _out_messages,
-                        process.search_message,
-                        process.settings.clear_regex
-                    ));
-                    spawn_thread!(thread_error(
-                        stderr,
-                        _err_messages,
-                        process.settings.clear_regex
-                    ));
-
-                    vec![pre_count + 1, pre_count + 2]
-                }
-                MessageSettings::None => vec![],
-            };
+        if self.find_process(name).is_ok() {
+            return Err(anyhow!("Process '{name}' already exists."));
+        }
 
-        let main_messages = self.main_messages.clone();
-        let name = name.to_string();
+        let process = self.new_process(name, settings);
 
-        spawn_thread!(thread_exit(name, child, main_messages));
+        spawn_async_capture(&runtime, &process, &mut child, &self.taps)?;
 
-        if let ScrollSettings::Enable {
-            up: up_right,
-            down: down_left,
-        } = process.settings.scroll
-        {
-            for (scroll_status, messages) in [
-                (
-                    process.scroll_status_out.clone(),
-                    process.out_messages.clone(),
-                ),
-                (
-                    process.scroll_status_err.clone(),
-                    process.err_messages.clone(),
-                ),
-            ] {
-                let action_scroll = ActionScroll {
-                    status: scroll_status.clone(),
-                    messages: messages.clone(),
-                };
+        let awaited = child.id().map(|pid| process.set_awaited(pid));
 
-                self.inputs.write_with(|mut inputs| {
-                    inputs.push(Action::new(
-                        up_right.into_event_no_modifier(),
-                        ActionType::ScrollUp(action_scroll.clone()),
-                    ));
-                    inputs.push(Action::new(
-                        down_left.into_event_no_modifier(),
-                        ActionType::ScrollDown(action_scroll.clone()),
-                    ));
-                    inputs.push(Action::new(
-                        down_left.into_event(KeyModifiers::SHIFT),
-                        ActionType::StopScrolling(process.scroll_status_out.clone()),
-                    ));
-                    inputs.push(Action::new(
-                        down_left.into_event(KeyModifiers::SHIFT),
-                        ActionType::StopScrolling(process.scroll_status_err.clone()),
-                    ));
-                });
-            }
-        }
+        self.attach(process.clone())?;
 
-        if !focus_indexes.is_empty() {
-            self.inputs
-                .write_with(|mut inputs| inputs.push_focus(&focus_indexes))?;
-        }
+        runtime.spawn(task_exit(
+            process.name.clone(),
+            child,
+            awaited,
+            process.exit_status.clone(),
+            self.main_messages.clone(),
+            process.settings.on_exit,
+            self.registry(),
+        ));
 
-        Ok(())
+        Ok(ProcessHandle {
+            process,
+            registry: self.registry(),
+        })
     }
 
-    pub fn add_message<M>(&self, message: M)
+    pub fn add_reader<R>(
+        &self,
+        name: &str,
+        reader: R,
+        settings: ProcessSettings,
+    ) -> Result<ProcessHandle>
     where
-        M: ToString,
+        R: Read + Send + 'static,
     {
-        self.main_messages.write_with(|mut messages| {
-            messages.push(message.to_string());
-        });
-    }
+        if self.find_process(name).is_ok() {
+            return Err(anyhow!("Process '{name}' already exists."));
+        }
 
-    pub(crate) fn block_search_message<S, P>(&self, process: P, submsg: S) -> Result<String>
-    where
-        S: ToString,
-        P: ToString,
-    {
-        let process = process.to_string();
+        let process = self.new_process(name, settings);
+        let capture = capture(&process, &self.taps)?;
 
-        let process = self
-            .processes
-            .read_access()
-            .clone()
-            .into_iter()
-            .find(|p| p.name == process)
-            .ok_or(anyhow!("Process not found."))?;
+        let threads = match &process.settings.messages {
+            MessageSettings::None => vec![],
+            MessageSettings::Error => {
+                let _err_messages = process.err_messages.clone();
+
+                vec![CaptureTask::Thread(spawn_thread!(thread_error(
+                    reader,
+                    _err_messages,
+                    None,
+                    capture
+                )))]
+            }
+            // A reader is a single stream, shown as the output of the process.
+            _ => {
+                let _out_messages = process.out_messages.clone();
+                let _searches = process.searches.clone();
+
+                vec![CaptureTask::Thread(spawn_thread!(thread_output(
+                    reader,
+                    _out_messages,
+                    _searches,
+                    capture
+                )))]
+            }
+        };
 
-        process.search_message.write_with(|mut process| {
-            *process = Some(SearchMessage::new(submsg.to_string()));
+        process.capture_threads.write_with(|mut capture_threads| {
+            *capture_threads = threads;
         });
 
-        loop {
-            let message = process.search_message.write_with(|mut search_message| {
-                let message = search_message.as_ref().unwrap().message.clone();
-                if message.is_some() {
-                    *search_message = None;
-                }
-                message
-            });
+        self.attach(process.clone())?;
 
-            if let Some(message) = message {
-                return Ok(message);
-            }
+        Ok(ProcessHandle {
+            process,
+            registry: self.registry(),
+        })
+    }
 
-            sleep_thread();
+    pub fn add_channel(
+        &self,
+        name: &str,
+        lines: Receiver<String>,
+        settings: ProcessSettings,
+    ) -> Result<ProcessHandle> {
+        if self.find_process(name).is_ok() {
+            return Err(anyhow!("Process '{name}' already exists."));
         }
-    }
 
-    pub(crate) fn with_exit_callback<F: Fn() + Send + Sync + 'static>(&self, closure: F) {
-        self.exit_callback.write_with(|mut terminal| {
-            *terminal = Some(Box::new(closure));
+        let process = self.new_process(name, settings);
+        let capture = capture(&process, &self.taps)?;
+
+        let _name = process.name.clone();
+        let _out_messages = process.out_messages.clone();
+        let _searches = process.searches.clone();
+        let _main_messages = self.main_messages.clone();
+
+        let thread = spawn_thread!(thread_channel(
+            _name,
+            lines,
+            _out_messages,
+            _searches,
+            capture,
+            _main_messages
+        ));
+
+        process.capture_threads.write_with(|mut capture_threads| {
+            *capture_threads = vec![CaptureTask::Thread(thread)];
         });
-    }
 
-    pub(crate) fn kill(&self) {
-        ratatui::restore();
-        if let Some(callback) = self.exit_callback.read_access().as_ref() {
-            callback();
-        }
+        self.attach(process.clone())?;
 
-        std::process::exit(0);
+        Ok(ProcessHandle {
+            process,
+            registry: self.registry(),
+        })
     }
-}
 
-impl Drop for Terminal {
-    fn drop(&mut self) {
-        ratatui::restore();
-    }
-}
+    /// Show a process, whose output is already being captured, and bind its keys.
+    fn attach(&self, process: Process) -> Result<()> {
+        self.processes.write_with(|mut processes| {
+            if processes
+                .iter()
+                .any(|attached| attached.name == process.name)
+            {
+                return Err(anyhow!("Process '{}' already exists.", process.name));
+            }
 
-fn thread_output(
-    stdout: ChildStdout,
-    messages: SharedMessages,
-    search_message: Shared<Option<SearchMessage>>,
-    clear_regex: bool,
-) {
-    let regex = if clear_regex {
-        Some(Regex::new())
-    } else {
-        None
-    };
+            processes.push(process.clone());
 
-    for line in BufReader::new(stdout).lines() {
-        let line = line.expect("Failed to read line from stdout.");
-        let line = if let Some(regex) = &regex {
-            regex.clear(line)
-        } else {
-            line
-        };
+            Ok(())
+        })?;
 
-        messages.write_with(|mut messages| {
-            messages.push(line.clone());
-        });
+        // A detached process redraws the terminal it's attached to.
+        for messages in [&process.out_messages, &process.err_messages] {
+            messages.write_with(|mut messages| messages.set_redraw(self.control.redraw.clone()));
+        }
 
-        search_message.write_with(|mut maybe_search_message| {
-            if let Some(search_message) = maybe_search_message.as_mut() {
-                if line.contains(&search_message.submsg) {
-                    search_message.message = Some(line);
-                }
-            }
-        });
-    }
-}
+        self.watch_exit(&process);
 
-fn thread_error(stderr: ChildStderr, messages: SharedMessages, clear_regex: bool) {
-    let regex: Option<Regex> = if clear_regex {
-        Some(Regex::new())
-    } else {
-        None
-    };
+        self.events
+            .emit(TerminalEvent::ProcessAdded(process.name.clone()));
 
-    for line in BufReader::new(stderr).lines() {
-        let line = line.expect("Failed to read line from stderr.");
-        let line = if let Some(regex) = &regex {
-            regex.clear(line)
-        } else {
-            line
-        };
+        #[cfg(feature = "tui")]
+        self.bind_keys(&process);
 
-        messages.write_with(|mut messages| {
-            messages.push(line);
-        });
+        self.control.redraw.request();
+
+        Ok(())
     }
-}
 
-fn thread_exit(process_name: String, mut child: Child, main_messages: SharedMessages) {
-    let exit_status = match child.wait() {
-        Ok(status) => format!("ok: {status}."),
+    /// Bind the scroll keys of `process`, and the focus keys of the panes.
+    #[cfg(feature = "tui")]
+    fn bind_keys(&self, process: &Process) {
+        if let ScrollSettings::Enable {
+            up: up_right,
+            down: down_left,
+        } = process.settings.scroll
+        {
+            // Each pane keeps its own scroll, whichever pane is in full screen.
+            for ty in BlockType::of(&process.settings.messages) {
+                let (status, messages) = process.pane(&ty);
 
-        Err(err) => format!("fail with error: {err}."),
-    };
+                let action_scroll = ActionScroll {
+                    status: status.clone(),
+                    messages,
+                };
 
-    main_messages.write_with(|mut messages| {
-        messages.push(format!("Process '{process_name}' exited: {exit_status}"));
-    });
-}
+                self.inputs.write_with(|mut inputs| {
+                    inputs.push(
+                        Action::new(
+                            up_right.into_event_no_modifier(),
+                            ActionType::ScrollUp(action_scroll.clone()),
+                        )
+                        .owned_by(&process.name),
+                    );
+                    inputs.push(
+                        Action::new(
+                            down_left.into_event_no_modifier(),
+                            ActionType::ScrollDown(action_scroll.clone()),
+                        )
+                        .owned_by(&process.name),
+                    );
+                    inputs.push(
+                        Action::new(
+                            down_left.into_event(KeyModifiers::SHIFT),
+                            ActionType::StopScrolling(status),
+                        )
+                        .owned_by(&process.name),
+                    );
+                });
+            }
+        }
 
-fn thread_input(inputs: Shared<KeyBoardActions>) {
-    loop {
-        let event = crossterm::event::read().expect("Failed to read event.");
+        let panes = self.processes.read_with(|processes| focus_keys(&processes));
 
-        inputs.read_with(|inputs| {
-            inputs.apply_event(event);
-        });
+        self.inputs
+            .write_with(|mut inputs| inputs.set_focus_keys(panes));
     }
-}
-
-fn thread_draw(main_messages: SharedMessages, main_scroll: BaseStatus, processes: SharedProcesses) {
-    let mut terminal = ratatui::init();
 
-    let data = DrawCache::new(main_messages, main_scroll, processes);
+    fn watch_exit(&self, process: &Process) {
+        let main_messages = self.main_messages.clone();
+        let name = process.name.clone();
+        let child = process.child.clone();
+        let exit_status = process.exit_status.clone();
+        let on_exit = process.settings.on_exit;
+        let registry = self.registry();
+
+        let supervisor = process.supervised().then(|| Supervisor {
+            process: process.clone(),
+            taps: self.taps.clone(),
+        });
 
-    let mut cache = DrawCache::default_detach();
+        spawn_thread!(thread_exit(
+            name,
+            child,
+            exit_status,
+            main_messages,
+            on_exit,
+            registry,
+            supervisor
+        ));
+    }
 
-    loop {
-        let read = data.detach();
+    pub fn restart_process<P>(&self, process: P, mut child: Child, clear: bool) -> Result<()>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
 
-        if read == cache {
-            sleep_thread();
-            continue;
-        } else {
-            cache = read.clone();
+        // A tokio child isn't in the slot, its exit being reported by its task.
+        if process.awaited() && process.exit_status.read_access().is_none() {
+            return Err(anyhow!("Process '{}' is still running.", process.name));
         }
 
-        let DrawCache {
-            main_messages,
-            main_scroll,
-            processes,
-        } = read;
+        // The slot is emptied once the exit is reported. Otherwise, taking the previous child out
+        // stops its exit watcher, so the exit is reported here and never after the restart.
+        let previous = process.child.write_with(|mut slot| match slot.take() {
+            Some(mut previous) => match previous.try_wait() {
+                Ok(None) => {
+                    *slot = Some(previous);
+                    Err(anyhow!("Process '{}' is still running.", process.name))
+                }
+                _ => Ok(Some(previous)),
+            },
+            None => Ok(None),
+        })?;
 
-        terminal
-            .draw(|frame| {
-                if let Some(focus) = main_scroll.focus {
-                    if focus == 0 {
-                        render_frame(
-                            frame,
-                            frame.area(),
-                            "",
-                            BlockType::Main,
-                            BlockFocus::Exit,
-                            main_messages,
-                            &main_scroll.main_scroll,
-                        );
-                    } else {
-                        let mut index = 0;
-                        for i in processes {
-                            if let Some((ty, messages, scroll)) = match i.settings.messages {
-                                MessageSettings::Output => {
-                                    index += 1;
-
-                                    if index == focus {
-                                        Some((BlockType::Out, i.out_messages, i.scroll_status_out))
-                                    } else {
-                                        None
-                                    }
-                                }
-                                MessageSettings::Error => {
-                                    index += 1;
-
-                                    if index == focus {
-                                        Some((BlockType::Err, i.err_messages, i.scroll_status_err))
-                                    } else {
-                                        None
-                                    }
-                                }
-                                MessageSettings::All => {
-                                    index += 1;
-
-                                    if index == focus {
-                                        Some((BlockType::Out, i.out_messages, i.scroll_status_out))
-                                    } else if index + 1 == focus {
-                                        Some((BlockType::Err, i.err_messages, i.scroll_status_err))
-                                    } else {
-                                        index += 1;
-                                        None
-                                    }
-                                }
-                                MessageSettings::None => None,
-                            } {
-                                render_frame(
-                                    frame,
-                                    frame.area(),
-                                    i.name,
-                                    ty,
-                                    BlockFocus::Exit,
-                                    messages,
-                                    &scroll,
-                                );
-                                break;
-                            }
-                        }
-                    }
-                } else {
-                    let main_chunks = Layout::default()
-                        .direction(Direction::Horizontal)
-                        .constraints(if processes.is_empty() {
-                            vec![Constraint::Percentage(100)]
-                        } else {
-                            vec![Constraint::Percentage(30), Constraint::Percentage(70)]
-                        })
-                        .split(frame.area());
-
-                    render_frame(
-                        frame,
-                        main_chunks[0],
-                        "",
-                        BlockType::Main,
-                        BlockFocus::Enter(0),
-                        main_messages,
-                        &main_scroll.main_scroll,
-                    );
+        if let Some(mut previous) = previous {
+            let status = previous.wait()?;
 
-                    if processes.is_empty() {
-                        return;
-                    }
+            process.exit_status.write_with(|mut exit_status| {
+                *exit_status = Some(status);
+            });
 
-                    let processes_chunks = Layout::default()
-                        .direction(Direction::Horizontal)
-                        .constraints(vec![
-                            Constraint::Ratio(1, processes.len() as u32);
-                            processes.len()
-                        ])
-                        .split(main_chunks[1]);
-
-                    let mut focus = 0;
-
-                    for (index, process) in processes.into_iter().enumerate() {
-                        match process.settings.messages {
-                            MessageSettings::Output => {
-                                focus += 1;
-
-                                render_frame(
-                                    frame,
-                                    processes_chunks[index],
-                                    process.name,
-                                    BlockType::Out,
-                                    BlockFocus::Enter(focus),
-                                    process.out_messages,
-                                    &process.scroll_status_out,
-                                );
-                            }
-                            MessageSettings::Error => {
-                                focus += 1;
-
-                                render_frame(
-                                    frame,
-                                    processes_chunks[index],
-                                    process.name,
-                                    BlockType::Err,
-                                    BlockFocus::Enter(focus),
-                                    process.err_messages,
-                                    &process.scroll_status_err,
-                                );
-                            }
-                            MessageSettings::All => {
-                                let process_chunks = Layout::default()
-                                    .direction(Direction::Vertical)
-                                    .constraints([
-                                        Constraint::Percentage(70),
-                                        Constraint::Percentage(30),
-                                    ])
-                                    .split(processes_chunks[index]);
-
-                                focus += 1;
-                                render_frame(
-                                    frame,
-                                    process_chunks[0],
-                                    &process.name,
-                                    BlockType::Out,
-                                    BlockFocus::Enter(focus),
-                                    process.out_messages,
-                                    &process.scroll_status_out,
-                                );
-
-                                focus += 1;
-                                render_frame(
-                                    frame,
-                                    process_chunks[1],
-                                    process.name,
-                                    BlockType::Err,
-                                    BlockFocus::Enter(focus),
-                                    process.err_messages,
-                                    &process.scroll_status_err,
-                                );
-                            }
-                            MessageSettings::None => {}
-                        }
-                    }
-                }
-            })
-            .unwrap();
+            report_exit(
+                &process.name,
+                &Ok(status),
+                &self.main_messages,
+                &self.events,
+            );
+        }
 
-        sleep_thread();
-    }
-}
+        restart_capture(
+            &process,
+            &mut child,
+            clear,
+            &self.taps,
+            self.settings.poll_interval,
+        )?;
 
-fn render_frame<N>(
-    frame: &mut Frame,
-    chunk: Rect,
-    name: N,
-    ty: BlockType,
-    focus: BlockFocus,
-    messages: Vec<String>,
-    scroll: &ScrollStatus,
-) where
-    N: ToString,
-{
-    let select_message = if messages.len() == 0 {
-        None
-    } else {
-        Some(messages.len() - 1)
-    };
+        // Written into the slot, shared with every clone of the process.
+        process.child.write_with(|mut slot| *slot = Some(child));
 
-    let mut state = ListState::default().with_selected(select_message);
+        self.add_message(format!("Process '{}' restarted.", process.name));
 
-    let sub_title = match ty {
-        BlockType::Main => Line::from("Main").cyan().bold(),
-        BlockType::Out => Line::from("Out").light_green().bold(),
-        BlockType::Err => Line::from("Err").light_red().bold(),
-    };
+        self.watch_exit(&process);
 
-    let focus_txt = match focus {
-        BlockFocus::Enter(index) => format!("full screen: '{index}'"),
-        BlockFocus::Exit => format!("press 'Esc' to exit full screen"),
-    };
+        Ok(())
+    }
 
-    let mut block = Block::default()
-        .title(Line::from(name.to_string()).gray().bold().centered())
-        .title(sub_title.centered())
-        .title(Line::from(focus_txt).right_aligned().italic().dark_gray())
-        .borders(Borders::ALL);
+    pub fn detach_process<P>(&self, process: P) -> Result<ProcessHandle>
+    where
+        P: ToString,
+    {
+        let process = self.unregister(process)?;
 
-    let is_scrolling = if let Some(y) = scroll.y {
-        let offset = messages.len().saturating_sub(y as usize);
+        // Moving the child to a new slot stops the exit watcher of the old one.
+        let child = process.child.write_with(|mut child| child.take());
 
-        state.scroll_up_by(offset as u16);
+        Ok(ProcessHandle {
+            process: Process {
+                child: Shared::new(child),
+                ..process
+            },
+            registry: self.registry(),
+        })
+    }
 
-        block = block.title(
-            Line::from(format!(
-                "Scrolling: offset {offset} - press 'shift + scroll_down' key to stop scrolling."
-            ))
-            .bold()
-            .left_aligned()
-            .yellow(),
-        );
+    pub fn remove_process<P>(&self, process: P) -> Result<()>
+    where
+        P: ToString,
+    {
+        // The child is left in its slot, so its exit is still reported.
+        self.unregister(process)?;
+
+        Ok(())
+    }
+
+    /// Remove the panes and key bindings of a process, renumbering the focus of the others.
+    fn unregister<P>(&self, process: P) -> Result<Process>
+    where
+        P: ToString,
+    {
+        let name = process.to_string();
+
+        self.unregister_where(|process| process.name == name)
+    }
+
+    fn unregister_where<F>(&self, matches: F) -> Result<Process>
+    where
+        F: Fn(&Process) -> bool,
+    {
+        self.registry().unregister_where(matches)
+    }
+
+    fn registry(&self) -> Registry {
+        Registry {
+            processes: self.processes.clone(),
+            status: self.status.clone(),
+            #[cfg(feature = "tui")]
+            inputs: self.inputs.clone(),
+            events: self.events.clone(),
+            control: self.control.clone(),
+        }
+    }
+
+    pub fn reattach_process(&self, handle: ProcessHandle) -> Result<()> {
+        self.attach(handle.process)
+    }
+
+    pub fn add_message<M>(&self, message: M)
+    where
+        M: ToString,
+    {
+        self.main_messages.write_with(|mut messages| {
+            messages.push(Message::new(message.to_string(), Stream::Out));
+        });
+    }
+
+    pub fn add_error_message<M>(&self, message: M)
+    where
+        M: ToString,
+    {
+        self.main_messages.write_with(|mut messages| {
+            messages.push(Message::new(message.to_string(), Stream::Err));
+        });
+    }
+
+    /// Print a message in the Main section tagged with `source`, to show it alone with
+    /// `set_source_filter`.
+    pub fn add_source_message<S, M>(&self, source: S, message: M)
+    where
+        S: ToString,
+        M: ToString,
+    {
+        self.main_messages.write_with(|mut messages| {
+            messages.push(Message::new(message.to_string(), Stream::Out).with_source(source));
+        });
+    }
+
+    /// Print a message in the Main section, colored by `level`, unless it's below
+    /// `TerminalSettings::min_level`.
+    pub fn add_level_message<M>(&self, level: Level, message: M)
+    where
+        M: ToString,
+    {
+        self.add_level_message_from(level, None, message);
+    }
+
+    /// As `add_level_message`, tagging the message with `source` if any.
+    pub(crate) fn add_level_message_from<M>(&self, level: Level, source: Option<&str>, message: M)
+    where
+        M: ToString,
+    {
+        if level < self.settings.min_level {
+            return;
+        }
+
+        let stream = match level {
+            Level::Error => Stream::Err,
+            _ => Stream::Out,
+        };
+
+        let mut message = Message::new(message.to_string(), stream).with_level(level);
+        message.source = source.map(str::to_string);
+
+        self.main_messages
+            .write_with(|mut messages| messages.push(message));
+    }
+
+    pub fn block_search_message<S, P>(&self, process: P, submsg: S) -> Result<String>
+    where
+        S: ToString,
+        P: ToString,
+    {
+        self.block_search_hit(process, submsg).map(|hit| hit.line)
+    }
+
+    pub fn block_search_hit<S, P>(&self, process: P, submsg: S) -> Result<SearchHit>
+    where
+        S: ToString,
+        P: ToString,
+    {
+        self.search_message(process, SearchPattern::Substring(submsg.to_string()), None)?
+            .ok_or(anyhow!("Search ended without a message."))
+    }
+
+    pub fn block_search_regex<P>(&self, process: P, pattern: &str, group: bool) -> Result<String>
+    where
+        P: ToString,
+    {
+        let regex = regex::Regex::new(pattern)?;
+
+        let pattern = if group {
+            if regex.captures_len() < 2 {
+                return Err(anyhow!("Pattern '{pattern}' has no capture group."));
+            }
+
+            SearchPattern::RegexGroup(regex)
+        } else {
+            SearchPattern::Regex(regex)
+        };
+
+        self.search_message(process, pattern, None)?
+            .map(|hit| hit.line)
+            .ok_or(anyhow!("Search ended without a message."))
+    }
+
+    pub fn block_search_message_timeout<S, P>(
+        &self,
+        process: P,
+        submsg: S,
+        timeout: Duration,
+    ) -> Result<Option<String>>
+    where
+        S: ToString,
+        P: ToString,
+    {
+        Ok(self
+            .search_message(
+                process,
+                SearchPattern::Substring(submsg.to_string()),
+                Some(timeout),
+            )?
+            .map(|hit| hit.line))
+    }
+
+    pub fn try_search_message<S, P>(&self, process: P, submsg: S) -> Result<Option<String>>
+    where
+        S: ToString,
+        P: ToString,
+    {
+        Ok(self.try_search_hit(process, submsg)?.map(|hit| hit.line))
+    }
+
+    pub fn try_search_hit<S, P>(&self, process: P, submsg: S) -> Result<Option<SearchHit>>
+    where
+        S: ToString,
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+
+        Ok(find_message(
+            &process.out_messages,
+            &SearchPattern::Substring(submsg.to_string()),
+            process.settings.messages == MessageSettings::Combined,
+        ))
+    }
+
+    pub fn wait_for_lines<P>(
+        &self,
+        process: P,
+        lines: usize,
+        timeout: Option<Duration>,
+    ) -> Result<()>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+        let (_, messages) = self.first_pane(&process.name)?;
+        let start = Instant::now();
+
+        loop {
+            // Checked before counting, so that the last lines captured are counted.
+            let ended = process.capture_ended();
+            let captured = messages.read_access().end();
+
+            if captured >= lines {
+                return Ok(());
+            }
+
+            if ended {
+                return Err(anyhow!(
+                    "Output of '{}' ended after {captured} of {lines} lines.",
+                    process.name
+                ));
+            }
+
+            if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+                return Err(anyhow!(
+                    "Timed out waiting for {lines} lines of '{}', {captured} captured.",
+                    process.name
+                ));
+            }
+
+            sleep(self.settings.poll_interval);
+        }
+    }
+
+    /// Search a pattern in the output of a process, first in the lines already captured,
+    /// then in the next ones until the timeout expires or the capture ends.
+    fn search_message<P>(
+        &self,
+        process: P,
+        pattern: SearchPattern,
+        timeout: Option<Duration>,
+    ) -> Result<Option<SearchHit>>
+    where
+        P: ToString,
+    {
+        Ok(search_process(
+            &self.find_process(process)?,
+            pattern,
+            timeout,
+            &self.events,
+            self.settings.poll_interval,
+        ))
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn set_pane_header<P>(&self, process: P, header: Line<'static>) -> Result<()>
+    where
+        P: ToString,
+    {
+        self.find_process(process)?
+            .decorations
+            .write_with(|mut decorations| {
+                decorations.header = Decorations::non_empty(header);
+            });
+
+        self.control.redraw.request();
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn set_pane_footer<P>(&self, process: P, footer: Line<'static>) -> Result<()>
+    where
+        P: ToString,
+    {
+        self.find_process(process)?
+            .decorations
+            .write_with(|mut decorations| {
+                decorations.footer = Decorations::non_empty(footer);
+            });
+
+        self.control.redraw.request();
+
+        Ok(())
+    }
+
+    pub fn pipe_to<P>(&self, process: P, mut command: Command) -> Result<()>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+        let program = command.get_program().to_string_lossy().to_string();
+
+        let mut child = command.stdin(Stdio::piped()).spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to get stdin on command: {program}"))?;
+
+        let (sender, receiver) = channel();
+
+        process.subscribers.write_with(|mut subscribers| {
+            subscribers.push(sender);
+        });
+
+        let_clone!(
+            self.main_messages.clone(),
+            main_messages | _main_messages: SharedMessages
+        );
+
+        let pipe_name = format!("{} | {program}", process.name);
+
+        spawn_thread!(thread_pipe(
+            process.name,
+            program,
+            stdin,
+            receiver,
+            _main_messages
+        ));
+
+        let child = Shared::new(Some(child));
+        let registry = self.registry();
+
+        spawn_thread!(thread_exit(
+            pipe_name,
+            child,
+            Default::default(),
+            main_messages,
+            OnExit::Keep,
+            registry,
+            None
+        ));
+
+        Ok(())
+    }
+
+    pub fn send_input<P>(&self, process: P, data: &str) -> Result<()>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+
+        if process.child.read_access().is_none() {
+            return Err(anyhow!("Process '{}' has exited.", process.name));
+        }
+
+        process.stdin.write_with(|mut stdin| {
+            let stdin = stdin
+                .as_mut()
+                .ok_or_else(|| anyhow!("Process '{}' has no piped stdin.", process.name))?;
+
+            writeln!(stdin, "{data}")?;
+            stdin.flush()?;
+
+            Ok(())
+        })
+    }
+
+    pub fn process_exit_status<P>(&self, process: P) -> Result<Option<ExitStatus>>
+    where
+        P: ToString,
+    {
+        Ok(*self.find_process(process)?.exit_status.read_access())
+    }
+
+    /// Run `f` on the child of a process, failing if it already exited.
+    fn with_running_child<P, T, F>(&self, process: P, f: F) -> Result<T>
+    where
+        P: ToString,
+        F: FnOnce(&mut Child) -> io::Result<T>,
+    {
+        let process = self.find_process(process)?;
+
+        process.child.write_with(|mut child| {
+            // A child which exited may be reaped, freeing its pid for another process.
+            let Some(child) = child.as_mut() else {
+                return Err(anyhow!("Process '{}' has exited.", process.name));
+            };
+
+            if !matches!(child.try_wait(), Ok(None)) {
+                return Err(anyhow!("Process '{}' has exited.", process.name));
+            }
+
+            Ok(f(child)?)
+        })
+    }
+
+    #[cfg(unix)]
+    pub fn signal_process<P>(&self, process: P, signal: i32) -> Result<()>
+    where
+        P: ToString,
+    {
+        self.with_running_child(process, |child| send_signal(child, signal))
+    }
+
+    pub fn terminate_process<P>(&self, process: P) -> Result<()>
+    where
+        P: ToString,
+    {
+        self.with_running_child(process, terminate)
+    }
+
+    pub fn process_info<P>(&self, process: P) -> Result<ProcessInfo>
+    where
+        P: ToString,
+    {
+        Ok(self.find_process(process)?.info())
+    }
+
+    pub fn wait_process_exit<P>(&self, process: P) -> Result<ExitStatus>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+
+        loop {
+            // The exit status is stored before the child is released, or no longer awaited.
+            let running = process.child.read_access().is_some() || process.awaited();
+
+            if let Some(exit_status) = *process.exit_status.read_access() {
+                return Ok(exit_status);
+            }
+
+            if !running {
+                return Err(anyhow!(
+                    "Failed to get the exit status of process '{}'.",
+                    process.name
+                ));
+            }
+
+            sleep(self.settings.poll_interval);
+        }
+    }
+
+    pub fn output_rate<P>(&self, process: P) -> Result<f64>
+    where
+        P: ToString,
+    {
+        Ok(self.find_process(process)?.counters.read_access().rate())
+    }
+
+    pub fn reset_counters<P>(&self, process: P) -> Result<()>
+    where
+        P: ToString,
+    {
+        self.find_process(process)?
+            .counters
+            .write_with(|mut counters| counters.reset());
+
+        Ok(())
+    }
+
+    pub fn process_output<P>(&self, process: P) -> Result<Vec<String>>
+    where
+        P: ToString,
+    {
+        Ok(snapshot(&self.find_process(process)?.out_messages))
+    }
+
+    pub fn process_errors<P>(&self, process: P) -> Result<Vec<String>>
+    where
+        P: ToString,
+    {
+        Ok(snapshot(&self.find_process(process)?.err_messages))
+    }
+
+    pub fn clear_process<P>(&self, process: P) -> Result<()>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+
+        for (messages, scroll_status) in [
+            (&process.out_messages, &process.scroll_status_out),
+            (&process.err_messages, &process.scroll_status_err),
+        ] {
+            clear_pane(messages, scroll_status);
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_main(&self) {
+        clear_pane(&self.main_messages, &self.main_scroll);
+    }
+
+    pub fn set_status<S>(&self, text: S)
+    where
+        S: ToString,
+    {
+        self.status.status_text.write_with(|mut status_text| {
+            *status_text = Some(text.to_string());
+        });
+
+        self.control.redraw.request();
+    }
+
+    pub fn clear_status(&self) {
+        self.status.status_text.write_with(|mut status_text| {
+            *status_text = None;
+        });
+
+        self.control.redraw.request();
+    }
+
+    pub fn main_messages(&self) -> Vec<String> {
+        snapshot(&self.main_messages)
+    }
+
+    pub fn merged_messages(&self) -> Vec<String> {
+        snapshot(&self.taps.merged)
+    }
+
+    pub fn set_merged_view(&self, merged: bool) {
+        self.status.merged.write_with(|mut status| *status = merged);
+
+        self.control.redraw.request();
+    }
+
+    pub fn set_source_filter(&self, source: Option<&str>) {
+        self.status
+            .source
+            .write_with(|mut status| *status = source.map(str::to_string));
+
+        self.control.redraw.request();
+    }
+
+    pub fn set_focus(&self, process: Option<&str>) -> Result<()> {
+        let focus = match process {
+            Some(process) => {
+                let processes = self.processes.read_access();
+
+                let index = processes
+                    .iter()
+                    .position(|p| p.name == process)
+                    .ok_or(anyhow!("Process not found."))?;
+
+                if processes[index].settings.messages == MessageSettings::None {
+                    return Err(anyhow!("Process '{process}' has no pane."));
+                }
+
+                // Focus of the first pane of the process.
+                Some(count_panes(&processes[..index]) + 1)
+            }
+            None => None,
+        };
+
+        let focused = self.current_focus();
+
+        self.status.focus.write_with(|mut current| *current = focus);
+        self.status.filter.write_with(|mut filter| *filter = None);
+
+        let now_focused = self.current_focus();
+
+        if now_focused != focused {
+            self.events.emit(TerminalEvent::FocusChanged(now_focused));
+        }
+
+        self.control.redraw.request();
+
+        Ok(())
+    }
+
+    pub fn scroll_to<P>(&self, process: P, index: usize) -> Result<()>
+    where
+        P: ToString,
+    {
+        let (status, messages) = self.first_pane(process)?;
+        let messages = messages.read_access();
+        let first = messages.end() - messages.len();
+
+        status.write_with(|mut scroll| {
+            // The view is anchored below the line, a line already dropped showing the oldest one.
+            let y = (index.max(first) + 1).min(messages.end());
+
+            // Anchoring to the last message follows the tail again.
+            scroll.y = (y < messages.end()).then_some(y);
+        });
+
+        self.control.redraw.request();
+
+        Ok(())
+    }
+
+    pub fn get_scroll<P>(&self, process: P) -> Result<ScrollStatus>
+    where
+        P: ToString,
+    {
+        let (status, _) = self.first_pane(process)?;
+        let status = status.read_access().clone();
+
+        Ok(status)
+    }
+
+    pub fn set_scroll<P>(&self, process: P, x: u16, y: Option<usize>) -> Result<()>
+    where
+        P: ToString,
+    {
+        let (status, messages) = self.first_pane(process)?;
+        let messages = messages.read_access();
+        let first = messages.end() - messages.len();
+
+        status.write_with(|mut status| {
+            status.x = x;
+            // Anchored at least below the oldest message, and following the tail from its end.
+            status.y = y
+                .map(|y| y.clamp((first + 1).min(messages.end()), messages.end()))
+                .filter(|&y| y < messages.end());
+        });
+
+        self.control.redraw.request();
+
+        Ok(())
+    }
+
+    /// Scroll status and messages of the first pane of a process.
+    fn first_pane<P>(&self, process: P) -> Result<(Shared<ScrollStatus>, SharedMessages)>
+    where
+        P: ToString,
+    {
+        let process = self.find_process(process)?;
+
+        BlockType::of(&process.settings.messages)
+            .first()
+            .map(|ty| process.pane(ty))
+            .ok_or_else(|| anyhow!("Process '{}' has no pane.", process.name))
+    }
+
+    pub fn search_and_focus<S, P>(&self, process: P, submsg: S) -> Result<SearchHit>
+    where
+        S: ToString,
+        P: ToString,
+    {
+        let process = process.to_string();
+        let submsg = submsg.to_string();
+        let hit = self.block_search_hit(&process, &submsg)?;
+
+        self.set_focus(Some(&process))?;
+        self.scroll_to(&process, hit.index)?;
+
+        // Replaces the highlight of the previous search.
+        clear_highlights(&self.processes);
+
+        self.find_process(&process)?
+            .decorations
+            .write_with(|mut decorations| {
+                decorations.highlight = regex::Regex::new(&regex::escape(&submsg)).ok();
+            });
+
+        self.control.redraw.request();
+
+        Ok(hit)
+    }
+
+    pub fn current_focus(&self) -> Option<String> {
+        focused_process(&self.processes, &self.status.focus)
+    }
+
+    pub fn subscribe(&self) -> Receiver<TerminalEvent> {
+        self.events.subscribe()
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn render_to_string(&self, width: u16, height: u16) -> String {
+        let data = DrawCache::new(
+            self.main_messages.clone(),
+            self.taps.merged.clone(),
+            self.status.clone(),
+            self.processes.clone(),
+        );
+
+        render_snapshot(
+            &data,
+            width,
+            height,
+            DrawOptions::new(&self.settings),
+            self.settings.poll_interval,
+        )
+    }
+
+    pub(crate) fn with_exit_callback<F: Fn() + Send + Sync + 'static>(&self, closure: F) {
+        self.control.exit_callback.write_with(|mut terminal| {
+            *terminal = Some(Box::new(closure));
+        });
+    }
+
+    /// Fails when no key is read, in headless mode or for an instance, as `handler` would
+    /// never run.
+    #[cfg(feature = "tui")]
+    pub fn add_key_action<F>(
+        &self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        handler: F,
+    ) -> Result<()>
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        if !self.control.screen || *HEADLESS {
+            bail!("The keys aren't read by a terminal that isn't drawn on the screen.");
+        }
+
+        self.inputs.write_with(|mut inputs| {
+            inputs.push(Action::new(
+                code.into_event(modifiers),
+                ActionType::Custom(Arc::new(handler)),
+            ));
+        });
+
+        Ok(())
+    }
+
+    pub fn set_line_tap<F>(&self, closure: F)
+    where
+        F: Fn(&str, Stream, &str) + Send + Sync + 'static,
+    {
+        self.taps.line_tap.write_with(|mut line_tap| {
+            *line_tap = Some(Box::new(closure));
+        });
+    }
+
+    /// Process whose lines are printed to stdout with `echo`.
+    fn new_process(&self, name: &str, settings: ProcessSettings) -> Process {
+        #[cfg_attr(not(feature = "tui"), allow(unused_mut))]
+        let mut process = Process::new(name.to_string(), settings);
+
+        // Picked once, so that it doesn't change as other processes are added or removed.
+        #[cfg(feature = "tui")]
+        {
+            let panes = self
+                .processes
+                .read_access()
+                .iter()
+                .filter(|process| process.settings.messages != MessageSettings::None)
+                .count();
+
+            process.accent = process
+                .settings
+                .color
+                .unwrap_or(PALETTE[panes % PALETTE.len()]);
+        }
+
+        if self.echo {
+            for messages in [&process.out_messages, &process.err_messages] {
+                messages.write_with(|mut messages| messages.set_echo(format!("[{name}] ")));
+            }
+        }
+
+        process
+    }
+
+    fn find_process<P>(&self, process: P) -> Result<Process>
+    where
+        P: ToString,
+    {
+        let process = process.to_string();
+
+        self.processes
+            .read_access()
+            .iter()
+            .find(|p| p.name == process)
+            .cloned()
+            .ok_or(anyhow!("Process not found."))
+    }
+
+    pub(crate) fn kill(&self) {
+        self.control.close();
+        self.join_threads();
+    }
+
+    pub(crate) fn wait_closed(&self) {
+        self.control.shutdown.wait(Stage::Closed, None);
+        self.join_threads();
+    }
+
+    /// Wait for the draw, input and capture threads to stop, except the calling one, e.g. when
+    /// closing from a key handler. The ones still running after `JOIN_TIMEOUT` are left detached.
+    fn join_threads(&self) {
+        let mut threads = self
+            .threads
+            .write_with(|mut threads| std::mem::take(&mut *threads));
+
+        self.processes.read_with(|processes| {
+            for process in processes.iter() {
+                let tasks = std::mem::take(&mut *process.capture_threads.write_access());
+
+                // The tasks of a runtime end along with their pipes, or with the runtime.
+                threads.extend(tasks.into_iter().filter_map(CaptureTask::thread));
+            }
+        });
+
+        let current = std::thread::current().id();
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+
+        for thread in threads {
+            if thread.thread().id() == current {
+                continue;
+            }
+
+            while !thread.is_finished() && Instant::now() < deadline {
+                sleep(self.settings.poll_interval);
+            }
+
+            if thread.is_finished() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        self.control.close();
+    }
+}
+
+/// Wait for a tokio child to exit, recording its status and reporting it like `thread_exit`.
+/// The child isn't restarted.
+#[cfg(feature = "tokio")]
+async fn task_exit(
+    process_name: String,
+    mut child: tokio::process::Child,
+    _awaited: Option<AwaitedExit>,
+    exit_status: Shared<Option<ExitStatus>>,
+    main_messages: SharedMessages,
+    on_exit: OnExit,
+    registry: Registry,
+) {
+    let exited = child.wait().await;
+
+    if let Ok(status) = &exited {
+        exit_status.write_with(|mut exit_status| *exit_status = Some(*status));
+    }
+
+    report_exit(&process_name, &exited, &main_messages, &registry.events);
+
+    if let OnExit::Remove(delay) = on_exit {
+        tokio::time::sleep(delay).await;
+        remove_exited(&registry, &exit_status);
+    }
+}
+
+fn thread_pipe(
+    process_name: String,
+    program: String,
+    mut stdin: ChildStdin,
+    lines: Receiver<String>,
+    main_messages: SharedMessages,
+) {
+    for line in lines {
+        if writeln!(stdin, "{line}")
+            .and_then(|_| stdin.flush())
+            .is_err()
+        {
+            main_messages.write_with(|mut messages| {
+                messages.push(
+                    Message::new(
+                        format!(
+                            "Pipe from '{process_name}' to '{program}' closed, stop forwarding."
+                        ),
+                        Stream::Out,
+                    )
+                    .with_source(&process_name),
+                );
+            });
+
+            break;
+        }
+    }
+}
+
+/// Wait for the child to exit, storing its exit status before releasing it from its slot.
+/// Stops silently if the child is taken out of the slot before, or replaced by a restart.
+fn thread_exit(
+    process_name: String,
+    child: Shared<Option<Child>>,
+    exit_status: Shared<Option<ExitStatus>>,
+    main_messages: SharedMessages,
+    on_exit: OnExit,
+    registry: Registry,
+    supervisor: Option<Supervisor>,
+) {
+    let mut restarts = 0;
+    let mut delay = supervisor
+        .as_ref()
+        .map(|supervisor| supervisor.process.settings.restart_delay)
+        .unwrap_or_default();
+
+    loop {
+        let started = Instant::now();
+
+        let Some(exited) = wait_exit(&child, &exit_status, registry.control.poll_interval) else {
+            return;
+        };
+
+        report_exit(&process_name, &exited, &main_messages, &registry.events);
+
+        let Some(supervisor) = &supervisor else {
+            break;
+        };
+
+        let success = exited.as_ref().is_ok_and(ExitStatus::success);
+
+        if !supervisor.process.settings.restart.fires(success, restarts) {
+            break;
+        }
+
+        // A child running for long enough isn't crash looping.
+        if started.elapsed() >= MAX_RESTART_DELAY {
+            delay = supervisor.process.settings.restart_delay;
+        }
+
+        sleep(delay);
+        delay = (delay * 2).min(MAX_RESTART_DELAY);
+
+        // Meanwhile, the terminal may be closing, or the process removed or restarted.
+        let supervised = registry.control.shutdown.stage() == Stage::Open
+            && exit_status.read_access().is_some()
+            && registry.processes.read_with(|processes| {
+                processes
+                    .iter()
+                    .any(|process| process.exit_status.ptr_eq(&exit_status))
+            });
+
+        if !supervised {
+            return;
+        }
+
+        let message = match supervisor.restart(registry.control.poll_interval) {
+            Ok(()) => {
+                restarts += 1;
+
+                registry
+                    .events
+                    .emit(TerminalEvent::ProcessRestarted(process_name.clone()));
+
+                format!("Process '{process_name}' restarted ({restarts} restarts).")
+            }
+            Err(err) => format!("Process '{process_name}' failed to restart: {err}"),
+        };
+
+        main_messages.write_with(|mut messages| {
+            messages.push(Message::new(message, Stream::Out).with_source(&process_name));
+        });
+
+        // The exit status is only reset by a successful restart.
+        if exit_status.read_access().is_some() {
+            break;
+        }
+    }
+
+    if let OnExit::Remove(delay) = on_exit {
+        sleep(delay);
+        remove_exited(&registry, &exit_status);
+    }
+}
+
+/// Note the exit of a process in the Main section, and emit its event.
+fn report_exit(
+    process_name: &str,
+    exited: &io::Result<ExitStatus>,
+    main_messages: &SharedMessages,
+    events: &Events,
+) {
+    main_messages.write_with(|mut messages| {
+        messages.push(
+            Message::new(exit_message(process_name, exited), Stream::Out).with_source(process_name),
+        );
+    });
+
+    events.emit(TerminalEvent::ProcessExited {
+        name: process_name.to_string(),
+        status: exited.as_ref().ok().copied(),
+    });
+}
+
+/// Note of the exit of a process, telling a failure from a success.
+fn exit_message(process_name: &str, exited: &io::Result<ExitStatus>) -> String {
+    match exited {
+        Ok(status) if status.success() => format!("Process '{process_name}' exited: ok: {status}."),
+        Ok(status) => format!("Process '{process_name}' exited: failed: {status}."),
+        Err(err) => format!("Process '{process_name}' exited: fail with error: {err}."),
+    }
+}
+
+/// Remove the process whose exit status is `exit_status`, once `OnExit::Remove` expired.
+fn remove_exited(registry: &Registry, exit_status: &Shared<Option<ExitStatus>>) {
+    // Fails when the process was already removed or detached. A restarted process is kept.
+    let _ = registry.unregister_where(|process| {
+        process.exit_status.ptr_eq(exit_status) && process.exit_status.read_access().is_some()
+    });
+}
+
+/// Wait for the child in the slot to exit, recording its status. `None` if it's taken out of
+/// the slot meanwhile, or replaced by a restart.
+fn wait_exit(
+    child: &Shared<Option<Child>>,
+    exit_status: &Shared<Option<ExitStatus>>,
+    poll_interval: Duration,
+) -> Option<io::Result<ExitStatus>> {
+    let mut watched = None;
+
+    loop {
+        let status = child.write_with(|mut slot| {
+            let id = slot.as_ref().map(Child::id);
+
+            // The new child of a restart has its own watcher.
+            if watched.is_some() && id != watched {
+                return None;
+            }
+
+            watched = id;
+
+            let status = slot.as_mut().map(Child::try_wait);
+
+            if let Some(Ok(Some(status))) = &status {
+                exit_status.write_with(|mut exit_status| {
+                    *exit_status = Some(*status);
+                });
+            }
+
+            if !matches!(status, Some(Ok(None))) {
+                *slot = None;
+            }
+
+            status
+        });
+
+        match status {
+            None => return None,
+            Some(Ok(None)) => sleep(poll_interval),
+            Some(Ok(Some(status))) => return Some(Ok(status)),
+            Some(Err(err)) => return Some(Err(err)),
+        }
+    }
+}
+
+/// What the exit watcher of a process needs to spawn it again, following its `RestartPolicy`.
+struct Supervisor {
+    process: Process,
+    taps: Taps,
+}
+
+impl Supervisor {
+    fn restart(&self, poll_interval: Duration) -> Result<()> {
+        let respawn = self
+            .process
+            .settings
+            .respawn
+            .as_ref()
+            .ok_or(anyhow!("No respawn closure."))?;
+
+        let mut child = respawn.spawn()?;
+
+        restart_capture(&self.process, &mut child, false, &self.taps, poll_interval)?;
+
+        self.process.child.write_with(|mut slot| {
+            *slot = Some(child);
+        });
+
+        Ok(())
+    }
+}
+
+/// Show the lines printed by the program to its stdout, captured through `pipe`, in the Main
+/// section.
+#[cfg(feature = "tui")]
+fn thread_stdout(pipe: File, main_messages: SharedMessages) {
+    for line in BufReader::new(pipe).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+
+        main_messages.write_with(|mut messages| {
+            messages.push(Message::new(ANSI_REGEX.clear(line), Stream::Out));
+        });
+    }
+}
+
+/// Leave raw mode and the alternate screen, releasing the mouse too.
+#[cfg(feature = "tui")]
+fn restore_screen() {
+    // Drawn from now on on stdout, whose capture must end first.
+    restore_stdout();
+
+    // Releasing a mouse that wasn't captured is harmless.
+    let _ = crossterm::execute!(stdout(), DisableMouseCapture);
+
+    ratatui::restore();
+}
+
+/// Restore the screen before reporting the panic of any thread, so the message is readable and
+/// the terminal usable afterwards. The screen was already restored once `shutdown` is past
+/// `Stage::Restored`.
+#[cfg(feature = "tui")]
+fn set_panic_hook(shutdown: Shutdown) {
+    let hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        if shutdown.stage() < Stage::Restored {
+            restore_screen();
+        }
+
+        hook(info);
+    }));
+}
+
+/// Ask the children of the shown processes to terminate, killing the ones still running after
+/// `KILL_GRACE_PERIOD`. Detached processes are left to their handle.
+fn stop_children(processes: &SharedProcesses, poll_interval: Duration) {
+    let children = processes.read_with(|processes| {
+        processes
+            .iter()
+            .map(|process| process.child.clone())
+            .collect::<Vec<_>>()
+    });
+
+    let is_running = |child: &Shared<Option<Child>>| {
+        child.write_with(|mut child| {
+            child
+                .as_mut()
+                .is_some_and(|child| matches!(child.try_wait(), Ok(None)))
+        })
+    };
+
+    for child in children.iter().filter(|child| is_running(child)) {
+        child.write_with(|mut child| {
+            if let Some(child) = child.as_mut() {
+                let _ = terminate(child);
+            }
+        });
+    }
+
+    let deadline = Instant::now() + KILL_GRACE_PERIOD;
+
+    while Instant::now() < deadline && children.iter().any(is_running) {
+        sleep(poll_interval);
+    }
+
+    for child in children.iter().filter(|child| is_running(child)) {
+        child.write_with(|mut child| {
+            if let Some(child) = child.as_mut() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        });
+    }
+}
+
+/// Send SIGTERM to `child`, letting it clean up before exiting.
+#[cfg(unix)]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    send_signal(child, libc::SIGTERM)
+}
+
+/// Kill `child`, as there's no way to ask it to terminate.
+#[cfg(not(unix))]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+/// Send `signal` to `child`, which must not have been waited for yet.
+#[cfg(unix)]
+fn send_signal(child: &mut Child, signal: i32) -> io::Result<()> {
+    // SAFETY: `kill` has no memory safety requirements, and the pid is the one of a child that
+    // wasn't reaped yet, so it can't have been reused.
+    if unsafe { libc::kill(child.id() as libc::pid_t, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(feature = "tui")]
+fn thread_input(
+    inputs: Shared<KeyBoardActions>,
+    processes: SharedProcesses,
+    focus: Shared<Option<usize>>,
+    events: Events,
+    control: Control,
+    poll_interval: Duration,
+) {
+    while control.shutdown.stage() == Stage::Open {
+        // Poll, rather than block on the next event, to stop once the terminal is closed.
+        let event = match crossterm::event::poll(poll_interval) {
+            Ok(false) => continue,
+            Ok(true) => crossterm::event::read(),
+            Err(err) => Err(err),
+        };
+
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                // No key can be read anymore, so the terminal is closed as if requested.
+                control.close();
+                events.emit(TerminalEvent::Failed(format!(
+                    "Failed to read the terminal events: {err}"
+                )));
+
+                return;
+            }
+        };
+
+        let focused = focused_process(&processes, &focus);
+        let handlers = inputs.read_with(|inputs| inputs.apply_event(event));
+
+        let now_focused = focused_process(&processes, &focus);
+
+        if now_focused != focused {
+            events.emit(TerminalEvent::FocusChanged(now_focused));
+        }
+
+        // Focus, scroll, filter, or the size of the terminal may have changed.
+        control.redraw.request();
+
+        // Run without holding the actions, as the handlers may register new ones.
+        for handler in handlers {
+            handler();
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+#[allow(clippy::too_many_arguments)]
+fn thread_draw(
+    main_messages: SharedMessages,
+    merged_messages: SharedMessages,
+    main_scroll: BaseStatus,
+    processes: SharedProcesses,
+    events: Events,
+    control: Control,
+    manage_terminal: bool,
+    mouse: bool,
+    refresh: Duration,
+    options: DrawOptions,
+) {
+    let data = DrawCache::new(main_messages, merged_messages, main_scroll, processes);
+    let result = draw_frames(data, &control, manage_terminal, mouse, refresh, options);
+
+    if manage_terminal {
+        restore_screen();
+    }
+
+    DRAWING.store(false, Ordering::Release);
+
+    if let Err(err) = result {
+        // Nothing is drawn anymore, so the terminal is closed as if requested.
+        control.close();
+        events.emit(TerminalEvent::Failed(format!(
+            "Failed to draw the terminal: {err}"
+        )));
+    }
+
+    control.shutdown.advance(Stage::Restored);
+}
+
+/// Draw the frames of `data` on the screen until the terminal is closing.
+#[cfg(feature = "tui")]
+fn draw_frames(
+    data: DrawCache,
+    control: &Control,
+    manage_terminal: bool,
+    mouse: bool,
+    refresh: Duration,
+    options: DrawOptions,
+) -> io::Result<()> {
+    // The screen is drawn on stdout, or its copy while stdout is captured.
+    let mut terminal = ratatui::Terminal::new(CrosstermBackend::new(Screen))?;
+
+    // Otherwise the caller already set up raw mode and the alternate screen. The panic hook
+    // restoring the screen is already installed.
+    if manage_terminal {
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+
+        if mouse {
+            crossterm::execute!(terminal.backend_mut(), EnableMouseCapture)?;
+        }
+    }
+
+    let areas = data.main_scroll.areas.clone();
+    let mut snapshots = Snapshots::default();
+
+    // The first frame is drawn right away.
+    control.redraw.request();
+
+    loop {
+        control.redraw.wait(REDRAW_FALLBACK);
+
+        if control.shutdown.stage() >= Stage::Closing {
+            return Ok(());
+        }
+
+        // Skip the frame rather than waiting for a capture thread holding a lock.
+        let Some(read) = data.try_detach(&mut snapshots) else {
+            control.redraw.request();
+            sleep(refresh);
+            continue;
+        };
+
+        terminal.draw(|frame| {
+            let drawn = draw_frame(frame, read, options);
+
+            // Written by the next frame if the input thread is reading them.
+            if areas.try_write_with(|mut areas| *areas = drawn).is_none() {
+                control.redraw.request();
+            }
+        })?;
+
+        // Changes requested while drawing are coalesced into the next frame.
+        sleep(refresh);
+    }
+}
+
+/// Stop highlighting the matches of the last search focused.
+pub(crate) fn clear_highlights(processes: &SharedProcesses) {
+    processes.read_with(|processes| {
+        for process in processes.iter() {
+            process
+                .decorations
+                .write_with(|mut decorations| decorations.highlight = None);
+        }
+    });
+}
+
+/// Search a pattern in the output of `process`, first in the lines already captured, then in
+/// the next ones until the timeout expires or the capture ends.
+fn search_process(
+    process: &Process,
+    pattern: SearchPattern,
+    timeout: Option<Duration>,
+    events: &Events,
+    poll_interval: Duration,
+) -> Option<SearchHit> {
+    // Installed before scanning the captured lines, so a line captured in between can't be
+    // missed.
+    let hit = Shared::default();
+
+    process.searches.write_with(|mut searches| {
+        searches.push(SearchMessage {
+            pattern: pattern.clone(),
+            hit: hit.clone(),
+        });
+    });
+
+    let start = Instant::now();
+
+    let combined = process.settings.messages == MessageSettings::Combined;
+
+    let found = find_message(&process.out_messages, &pattern, combined).or_else(|| loop {
+        // Checked before the hit, so that the last lines captured are matched.
+        let ended = process.capture_ended();
+        let found = hit.read_access().clone();
+
+        if found.is_some() || ended {
+            break found;
+        }
+
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            break None;
+        }
+
+        sleep(poll_interval);
+    });
+
+    process.searches.write_with(|mut searches| {
+        searches.retain(|search| !search.hit.ptr_eq(&hit));
+    });
+
+    if let Some(hit) = &found {
+        events.emit(TerminalEvent::SearchFound {
+            process: process.name.clone(),
+            hit: hit.clone(),
+        });
+    }
+
+    found
+}
+
+/// Match of the first captured stdout line matching `pattern`, or stdout and stderr line for a
+/// combined pane.
+fn find_message(
+    messages: &SharedMessages,
+    pattern: &SearchPattern,
+    combined: bool,
+) -> Option<SearchHit> {
+    messages.read_with(|messages| {
+        let first = messages.end() - messages.len();
+
+        messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| combined || message.stream == Stream::Out)
+            .find_map(|(position, message)| {
+                pattern.matches(&message.text).map(|line| SearchHit {
+                    index: first + position,
+                    line,
+                })
+            })
+    })
+}
+
+fn count_panes(processes: &[Process]) -> usize {
+    processes
+        .iter()
+        .map(|process| BlockType::of(&process.settings.messages).len())
+        .sum()
+}
+
+/// Groups of the processes, in the order they're first used, shown as tabs.
+#[cfg(feature = "tui")]
+pub(crate) fn groups<'a, I>(settings: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a ProcessSettings>,
+{
+    let mut groups: Vec<String> = vec![];
+
+    for group in settings
+        .into_iter()
+        .filter_map(|settings| settings.group.as_ref())
+    {
+        if !groups.contains(group) {
+            groups.push(group.clone());
+        }
+    }
+
+    groups
+}
+
+/// Group of the tab shown, the first one if `group` isn't one of `groups` anymore.
+#[cfg(feature = "tui")]
+pub(crate) fn active_group<'a>(groups: &'a [String], group: &Option<String>) -> Option<&'a String> {
+    groups
+        .iter()
+        .find(|candidate| Some(*candidate) == group.as_ref())
+        .or(groups.first())
+}
+
+/// Show the tab after the current one, or the one before if `forward` is false.
+#[cfg(feature = "tui")]
+pub(crate) fn cycle_group(
+    group: &Shared<Option<String>>,
+    processes: &SharedProcesses,
+    forward: bool,
+) {
+    let groups =
+        processes.read_with(|processes| groups(processes.iter().map(|process| &process.settings)));
+
+    if groups.is_empty() {
+        return;
+    }
+
+    group.write_with(|mut group| {
+        let current = active_group(&groups, &group)
+            .and_then(|active| groups.iter().position(|candidate| candidate == active))
+            .unwrap_or(0);
+
+        let next = if forward {
+            (current + 1) % groups.len()
+        } else {
+            (current + groups.len() - 1) % groups.len()
+        };
+
+        *group = Some(groups[next].clone());
+    });
+}
+
+/// Show the messages of the source after the current one alone in the Main section, or all of
+/// them again after the last one.
+#[cfg(feature = "tui")]
+pub(crate) fn cycle_source(source: &Shared<Option<String>>, pane: &ActionPane) {
+    let Some((_, messages)) = focused_scroll(pane, 0) else {
+        return;
+    };
+
+    let sources = messages.read_with(|messages| messages.sources());
+
+    source.write_with(|mut source| {
+        let current = source
+            .as_ref()
+            .and_then(|current| sources.iter().position(|candidate| candidate == current));
+
+        *source = match current {
+            Some(index) => sources.get(index + 1).cloned(),
+            None if source.is_none() => sources.first().cloned(),
+            // Its messages were all trimmed.
+            None => None,
+        };
+    });
+}
+
+/// Name of the process whose pane is in full screen.
+fn focused_process(processes: &SharedProcesses, focus: &Shared<Option<usize>>) -> Option<String> {
+    // The Main section isn't a process.
+    let focus = focus.read_access().filter(|&focus| focus > 0)?;
+
+    let processes = processes.read_access();
+    let mut panes = 0;
+
+    processes
+        .iter()
+        .find(|process| {
+            panes += BlockType::of(&process.settings.messages).len();
+            panes >= focus
+        })
+        .map(|process| process.name.clone())
+}
+
+/// Focus key of each pane, set on the first pane of the processes with a `focus_key`, along
+/// with the name of the process.
+#[cfg(feature = "tui")]
+fn focus_keys(processes: &[Process]) -> Vec<Option<(KeyCode, String)>> {
+    processes
+        .iter()
+        .flat_map(|process| {
+            (0..BlockType::of(&process.settings.messages).len()).map(|pane| {
+                process
+                    .settings
+                    .focus_key
+                    .filter(|_| pane == 0)
+                    .map(|code| (code, process.name.clone()))
+            })
+        })
+        .collect()
+}
+
+/// Scroll status and messages of the pane at `focus`.
+#[cfg(feature = "tui")]
+pub(crate) fn focused_scroll(
+    pane: &ActionPane,
+    focus: usize,
+) -> Option<(Shared<ScrollStatus>, SharedMessages)> {
+    if focus == 0 && *pane.merged.read_access() {
+        return Some((pane.merged_scroll.clone(), pane.merged_messages.clone()));
+    }
+
+    if focus == 0 {
+        return Some((pane.main_scroll.clone(), pane.main_messages.clone()));
+    }
+
+    pane.processes
+        .read_access()
+        .iter()
+        .flat_map(|process| {
+            BlockType::of(&process.settings.messages)
+                .into_iter()
+                .map(move |ty| process.pane(&ty))
+        })
+        .nth(focus - 1)
+}
+
+/// Anchor the view of the pane at `focus` to the current tail, or let it follow the new
+/// messages again if it was anchored.
+#[cfg(feature = "tui")]
+pub(crate) fn toggle_follow(pane: &ActionPane, focus: usize) {
+    let Some((scroll, messages)) = focused_scroll(pane, focus) else {
+        return;
+    };
+
+    let end = messages.read_access().end();
+
+    scroll.write_with(|mut scroll| {
+        scroll.y = match scroll.y {
+            Some(_) => None,
+            None => Some(end),
+        };
+    });
+}
+
+/// Empty the messages of a pane and reset its view.
+///
+/// The positions of the messages are absolute, so the ones captured after the clear don't
+/// collide with the ones a scroll was anchored to, nor are they missed by a search in flight.
+pub(crate) fn clear_pane(messages: &SharedMessages, scroll_status: &Shared<ScrollStatus>) {
+    messages.write_with(|mut messages| messages.clear());
+
+    scroll_status.write_with(|mut scroll_status| {
+        *scroll_status = ScrollStatus::new(scroll_status.wrap);
+    });
+}
+
+/// Empty the pane at `focus`.
+#[cfg(feature = "tui")]
+pub(crate) fn clear_focused_pane(pane: &ActionPane, focus: usize) {
+    if let Some((scroll, messages)) = focused_scroll(pane, focus) {
+        clear_pane(&messages, &scroll);
+    }
+}
+
+/// Move the view of the pane at `focus` to the position returned by `to`, given the current one
+/// (the tail when following) and the position of the oldest message. Reaching the tail follows
+/// the new messages again.
+#[cfg(feature = "tui")]
+pub(crate) fn move_scroll<F>(pane: &ActionPane, focus: usize, to: F)
+where
+    F: FnOnce(usize, usize) -> usize,
+{
+    let Some((scroll, messages)) = focused_scroll(pane, focus) else {
+        return;
+    };
+
+    let (first, end) = messages.read_with(|messages| {
+        let first = messages.end() - messages.len();
+
+        ((first + 1).min(messages.end()), messages.end())
+    });
+
+    scroll.write_with(|mut scroll| {
+        let y = to(scroll.y.unwrap_or(end), first).clamp(first, end);
+
+        scroll.y = (y < end).then_some(y);
+    });
+}
+
+/// Follow again the tail of the pane at `focus`, or of every pane and the Main section when
+/// `None`, scrolled back to the start of the lines.
+#[cfg(feature = "tui")]
+pub(crate) fn stop_scrolling(pane: &ActionPane, focus: Option<usize>) {
+    let statuses = match focus {
+        Some(focus) => focused_scroll(pane, focus)
+            .map(|(status, _)| vec![status])
+            .unwrap_or_default(),
+        None => pane.processes.read_with(|processes| {
+            processes
+                .iter()
+                .flat_map(|process| {
+                    [
+                        process.scroll_status_out.clone(),
+                        process.scroll_status_err.clone(),
+                    ]
+                })
+                .chain([pane.main_scroll.clone(), pane.merged_scroll.clone()])
+                .collect()
+        }),
+    };
+
+    for status in statuses {
+        status.write_with(|mut status| {
+            status.y = None;
+            status.x = 0;
+        });
+    }
+}
+
+/// Anchor the view of the pane at `focus` to the next error line after the current anchor, or
+/// the previous one if `forward` is false. Nothing moves if there's none.
+#[cfg(feature = "tui")]
+pub(crate) fn jump_to_error(pane: &ActionPane, focus: usize, forward: bool) {
+    let Some((scroll, messages)) = focused_scroll(pane, focus) else {
+        return;
+    };
+
+    let messages = messages.read_access();
+    let first = messages.end() - messages.len();
+
+    scroll.write_with(|mut scroll| {
+        // Absolute position of the message at the bottom of the view.
+        let anchor = scroll.y.unwrap_or(messages.end()).saturating_sub(1);
+
+        let mut errors = messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.error)
+            .map(|(index, _)| first + index);
+
+        let found = if forward {
+            errors.find(|&position| position > anchor)
+        } else {
+            errors.filter(|&position| position < anchor).last()
+        };
+
+        if let Some(position) = found {
+            let y = position + 1;
+
+            // Anchoring to the last message follows the tail again.
+            scroll.y = (y < messages.end()).then_some(y);
+        }
+    });
+}
+
+/// Amount of messages scrolled by a page, the height of a full screen pane.
+#[cfg(feature = "tui")]
+pub(crate) fn page_height() -> usize {
+    crossterm::terminal::size()
+        .map_or(1, |(_, rows)| rows.saturating_sub(2) as usize)
+        .max(1)
+}
+
+/// Switch the pane at `focus` between wrapping and clipping the long lines.
+#[cfg(feature = "tui")]
+pub(crate) fn toggle_wrap(pane: &ActionPane, focus: usize) {
+    if let Some((scroll, _)) = focused_scroll(pane, focus) {
+        scroll.write_with(|mut scroll| {
+            scroll.wrap = !scroll.wrap;
+            scroll.x = 0;
+        });
+    }
+}
+
+/// Copy the messages of the focused pane to the clipboard, noting the result in the Main section.
+#[cfg(feature = "tui")]
+pub(crate) fn copy_focused_pane(pane: &ActionPane, focus: usize) {
+    let Some((_, messages)) = focused_scroll(pane, focus) else {
+        return;
+    };
+
+    let messages = messages.read_access().clone();
+
+    let text = messages
+        .iter()
+        .map(|message| message.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let result = set_clipboard(text, messages.len());
+
+    pane.main_messages.write_with(|mut main_messages| {
+        let (note, stream) = match result {
+            Ok(note) => (note, Stream::Out),
+            Err(err) => (
+                format!("Failed to copy to the clipboard: {err}"),
+                Stream::Err,
+            ),
+        };
+
+        main_messages.push(Message::new(note, stream));
+    });
+}
+
+/// Copy `text`, made of `lines` lines, to the system clipboard.
+#[cfg(all(feature = "tui", feature = "clipboard"))]
+fn set_clipboard(text: String, lines: usize) -> Result<String> {
+    // On Linux, the clipboard only holds the text while its owner is alive.
+    static CLIPBOARD: std::sync::Mutex<Option<arboard::Clipboard>> = std::sync::Mutex::new(None);
+
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    let clipboard = match clipboard.as_mut() {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new()?),
+    };
+
+    clipboard.set_text(text)?;
+
+    Ok(format!("Copied {lines} lines to the clipboard."))
+}
+
+/// Ask the terminal emulator to copy `text`, made of `lines` lines, to the clipboard through
+/// the OSC 52 escape sequence. Terminals without its support ignore it silently, so whether the
+/// text was copied isn't known.
+#[cfg(all(feature = "tui", not(feature = "clipboard")))]
+fn set_clipboard(text: String, lines: usize) -> Result<String> {
+    write!(Screen, "\x1b]52;c;{}\x07", base64(text.as_bytes())).and_then(|_| Screen.flush())?;
+
+    Ok(format!(
+        "Requested the copy of {lines} lines to the clipboard (OSC 52)."
+    ))
+}
+
+#[cfg(all(feature = "tui", not(feature = "clipboard")))]
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (*byte as u32) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+fn snapshot(messages: &SharedMessages) -> Vec<String> {
+    messages
+        .read_access()
+        .iter()
+        .map(|message| message.text.clone())
+        .collect()
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct Process<
+    O = SharedMessages,
+    E = SharedMessages,
+    S = Shared<ScrollStatus>,
+    SM = Shared<Searches>,
+    D = Shared<Decorations>,
+    SB = Shared<Subscribers>,
+    C = Shared<Counters>,
+    CH = Shared<Option<Child>>,
+    T = Shared<Vec<CaptureTask>>,
+    I = Shared<Option<ChildStdin>>,
+    X = Shared<Option<ExitStatus>>,
+    SP = Shared<Option<Spawned>>,
+> {
+    pub name: String,
+    /// Color of the panes and merged lines of the process: its `color` setting, or the palette
+    /// one for its position among the processes with panes when it was added.
+    #[cfg(feature = "tui")]
+    pub accent: Color,
+    pub out_messages: O,
+    pub err_messages: E,
+    pub settings: ProcessSettings,
+    pub scroll_status_out: S,
+    pub scroll_status_err: S,
+    pub searches: SM,
+    pub decorations: D,
+    pub subscribers: SB,
+    pub counters: C,
+    pub child: CH,
+    pub capture_threads: T,
+    pub stdin: I,
+    pub exit_status: X,
+    pub spawned: SP,
+}
+
+impl Process {
+    pub fn new(name: String, settings: ProcessSettings) -> Process {
+        let messages = || Shared::new(Messages::new(settings.max_lines, settings.timestamps));
+
+        Process {
+            #[cfg(feature = "tui")]
+            accent: settings.color.unwrap_or(PALETTE[0]),
+            out_messages: messages(),
+            err_messages: messages(),
+            name,
+            scroll_status_out: Shared::new(ScrollStatus::new(settings.wrap)),
+            scroll_status_err: Shared::new(ScrollStatus::new(settings.wrap)),
+            settings,
+            searches: Default::default(),
+            decorations: Default::default(),
+            subscribers: Default::default(),
+            counters: Default::default(),
+            child: Default::default(),
+            capture_threads: Default::default(),
+            stdin: Default::default(),
+            exit_status: Default::default(),
+            spawned: Default::default(),
+        }
+    }
+
+    /// Scroll status and messages of the pane of type `ty`, the stdout ones unless it's the
+    /// stderr pane.
+    fn pane(&self, ty: &BlockType) -> (Shared<ScrollStatus>, SharedMessages) {
+        match ty {
+            BlockType::Err => (self.scroll_status_err.clone(), self.err_messages.clone()),
+            _ => (self.scroll_status_out.clone(), self.out_messages.clone()),
+        }
+    }
+
+    /// Record the PID and start time of a newly spawned child.
+    pub fn set_spawned(&self, pid: u32) {
+        self.spawned.write_with(|mut spawned| {
+            *spawned = Some(Spawned {
+                pid,
+                at: SystemTime::now(),
+                awaited: false,
+            });
+        });
+    }
+
+    /// Record a newly spawned tokio child, awaited by `task_exit` until the returned guard is
+    /// dropped.
+    #[cfg(feature = "tokio")]
+    fn set_awaited(&self, pid: u32) -> AwaitedExit {
+        self.spawned.write_with(|mut spawned| {
+            *spawned = Some(Spawned {
+                pid,
+                at: SystemTime::now(),
+                awaited: true,
+            });
+        });
+
+        AwaitedExit(self.spawned.clone())
+    }
+
+    /// Whether the current child is a tokio one whose exit is still awaited.
+    fn awaited(&self) -> bool {
+        self.spawned
+            .read_access()
+            .is_some_and(|spawned| spawned.awaited)
+    }
+
+    fn info(&self) -> ProcessInfo {
+        let spawned = *self.spawned.read_access();
+
+        ProcessInfo {
+            pid: spawned.map(|spawned| spawned.pid),
+            started_at: spawned.map(|spawned| spawned.at),
+            running: spawned.is_some() && self.exit_status.read_access().is_none(),
+        }
+    }
+
+    /// Snapshot of the process for the draw thread, `None` instead of blocking if any of the
+    /// values is being written.
+    ///
+    /// `visible` is called in order for each pane of the process: the buffers of the panes it
+    /// returns false for aren't snapshotted, and are left empty.
+    #[cfg(feature = "tui")]
+    pub fn try_detach<F>(&self, snapshots: &mut Snapshots, mut visible: F) -> Option<DetachProcess>
+    where
+        F: FnMut() -> bool,
+    {
+        let mut out_messages = Arc::default();
+        let mut err_messages = Arc::default();
+
+        for ty in BlockType::of(&self.settings.messages) {
+            if !visible() {
+                continue;
+            }
+
+            match ty {
+                BlockType::Err => err_messages = snapshots.get(&self.err_messages)?,
+                _ => out_messages = snapshots.get(&self.out_messages)?,
+            }
+        }
+
+        Some(Process {
+            name: self.name.clone(),
+            accent: self.accent,
+            settings: self.settings.clone(),
+            out_messages,
+            err_messages,
+            scroll_status_out: self
+                .scroll_status_out
+                .try_read_with(|scroll_status_out| scroll_status_out.clone())?,
+            scroll_status_err: self
+                .scroll_status_err
+                .try_read_with(|scroll_status_err| scroll_status_err.clone())?,
+            searches: (),
+            decorations: self
+                .decorations
+                .try_read_with(|decorations| decorations.clone())?,
+            subscribers: (),
+            counters: self.counters.try_read_with(|counters| counters.stats())?,
+            child: (),
+            capture_threads: (),
+            stdin: (),
+            exit_status: self.exit_status.try_read_with(|exit_status| *exit_status)?,
+            spawned: (),
+        })
+    }
+
+    /// Whether the process is spawned again by its `RestartPolicy` once it exits.
+    fn supervised(&self) -> bool {
+        self.settings.restart != RestartPolicy::Never && self.settings.respawn.is_some()
+    }
+
+    /// Whether no line is captured anymore: the capture threads are done and the process isn't
+    /// spawned again by its `RestartPolicy`.
+    fn capture_ended(&self) -> bool {
+        !self.supervised()
+            && self
+                .capture_threads
+                .read_access()
+                .iter()
+                .all(CaptureTask::is_finished)
+    }
+}
+
+/// PID and start time of the current child of a process.
+#[derive(Clone, Copy)]
+pub(crate) struct Spawned {
+    pid: u32,
+    at: SystemTime,
+    /// Whether the exit of the child is awaited by `task_exit`, the child not being in the slot.
+    awaited: bool,
+}
+
+/// Clears `Spawned::awaited` once `task_exit` is over, even if its runtime was shut down first.
+#[cfg(feature = "tokio")]
+struct AwaitedExit(Shared<Option<Spawned>>);
+
+#[cfg(feature = "tokio")]
+impl Drop for AwaitedExit {
+    fn drop(&mut self) {
+        self.0.write_with(|mut spawned| {
+            if let Some(spawned) = spawned.as_mut() {
+                spawned.awaited = false;
+            }
+        });
+    }
+}
+
+/// State of the child of a process, returned by `process_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProcessInfo {
+    /// PID of the current child, `None` for the processes fed by a reader or a channel.
+    pub pid: Option<u32>,
+    /// When the current child was added or restarted.
+    pub started_at: Option<SystemTime>,
+    /// Whether the exit of the current child wasn't reported yet.
+    pub running: bool,
+}
+
+impl ProcessInfo {
+    /// Time elapsed since the child was started, while it's running.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.started_at
+            .filter(|_| self.running)
+            .and_then(|started_at| started_at.elapsed().ok())
+    }
+}
+
+/// Processes of a terminal along with its key bindings, to remove a process from it.
+#[derive(Clone)]
+pub(crate) struct Registry {
+    processes: SharedProcesses,
+    status: BaseStatus,
+    #[cfg(feature = "tui")]
+    inputs: Shared<KeyBoardActions>,
+    events: Events,
+    control: Control,
+}
+
+impl Registry {
+    /// Remove the panes and key bindings of the first process matching, renumbering the focus of
+    /// the others.
+    pub fn unregister_where<F>(&self, matches: F) -> Result<Process>
+    where
+        F: Fn(&Process) -> bool,
+    {
+        let (process, first_pane) = self.processes.write_with(|mut processes| {
+            let position = processes
+                .iter()
+                .position(matches)
+                .ok_or(anyhow!("Process not found."))?;
+
+            let first_pane = count_panes(&processes[..position]) + 1;
+            let process = processes.remove(position);
+
+            Ok::<_, anyhow::Error>((process, first_pane))
+        })?;
+
+        self.status
+            .remove_focus_panes(first_pane, BlockType::of(&process.settings.messages).len());
+
+        #[cfg(feature = "tui")]
+        {
+            let panes = self.processes.read_with(|processes| focus_keys(&processes));
+
+            self.inputs.write_with(|mut inputs| {
+                inputs.remove_owned(&process.name);
+                inputs.set_focus_keys(panes);
+            });
+        }
+
+        self.events
+            .emit(TerminalEvent::ProcessRemoved(process.name.clone()));
+
+        self.control.redraw.request();
+
+        Ok(process)
+    }
+}
+
+/// Handle to a process, returned by `add_process` and `detach_process`.
+///
+/// The output of a detached process keeps being captured in the background until it closes its
+/// stdout/stderr, so no line is lost if it's reattached with `reattach_process`.
+pub struct ProcessHandle {
+    process: Process,
+    /// Terminal the process was added to.
+    registry: Registry,
+}
+
+impl ProcessHandle {
+    pub fn name(&self) -> &str {
+        &self.process.name
+    }
+
+    /// Snapshot of the lines currently held in the output pane.
+    pub fn output(&self) -> Vec<String> {
+        snapshot(&self.process.out_messages)
+    }
+
+    /// Snapshot of the lines currently held in the error pane.
+    pub fn errors(&self) -> Vec<String> {
+        snapshot(&self.process.err_messages)
+    }
+
+    /// Like `block_search_message`, searching the output of this process.
+    pub fn search<S>(&self, submsg: S) -> Result<String>
+    where
+        S: ToString,
+    {
+        search_process(
+            &self.process,
+            SearchPattern::Substring(submsg.to_string()),
+            None,
+            &self.registry.events,
+            self.registry.control.poll_interval,
+        )
+        .map(|hit| hit.line)
+        .ok_or(anyhow!("Search ended without a message."))
+    }
+
+    /// Whether the exit of the process wasn't reported yet.
+    pub fn is_running(&self) -> bool {
+        self.process.exit_status.read_access().is_none()
+    }
+
+    /// PID, start time and state of the child, like `process_info`.
+    pub fn info(&self) -> ProcessInfo {
+        self.process.info()
+    }
+
+    /// Remove the process from the terminal, like `remove_process`.
+    /// Fails if this process isn't shown anymore, even if another one took its name.
+    pub fn remove(&self) -> Result<()> {
+        self.registry
+            .unregister_where(|process| process.out_messages.ptr_eq(&self.process.out_messages))?;
+
+        Ok(())
+    }
+
+    /// Take ownership of the child, `None` if it was already taken or its exit was reported.
+    /// Once taken, the exit of the process is no longer reported if reattached.
+    pub fn take_child(&self) -> Option<Child> {
+        self.process.child.write_with(|mut child| child.take())
+    }
+}
+
+/// Stream a captured line was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stream {
+    Out,
+    Err,
+}
+
+/// Search waiting for a line matching its pattern, among the others of the process.
+pub(crate) struct SearchMessage {
+    pub pattern: SearchPattern,
+    /// First line matching, also identifying the search to remove it once over.
+    pub hit: Shared<Option<SearchHit>>,
+}
+
+/// Line found by a search, returned by `block_search_hit` and `try_search_hit`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    /// Absolute position of the line in the output pane, counting the lines dropped over
+    /// `max_lines` or cleared, as the positions of the scroll.
+    pub index: usize,
+    /// What the search returns: the whole line, or the capture group of a group search.
+    pub line: String,
+}
+
+#[derive(Clone)]
+pub(crate) enum SearchPattern {
+    Substring(String),
+    Regex(regex::Regex),
+    /// Matches like `Regex`, but the search returns the first capture group.
+    RegexGroup(regex::Regex),
+}
+
+impl SearchPattern {
+    /// What the search returns if the line matches.
+    pub fn matches(&self, line: &str) -> Option<String> {
+        match self {
+            SearchPattern::Substring(submsg) => line.contains(submsg).then(|| line.to_string()),
+            SearchPattern::Regex(regex) => regex.is_match(line).then(|| line.to_string()),
+            SearchPattern::RegexGroup(regex) => regex
+                .captures(line)
+                .and_then(|captures| captures.get(1))
+                .map(|group| group.as_str().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum BlockType {
+    #[cfg(feature = "tui")]
+    Main,
+    /// Lines of every process, shown by the Main section instead of its messages.
+    #[cfg(feature = "tui")]
+    Merged,
+    Out,
+    Err,
+    Combined,
+}
+
+impl BlockType {
+    /// Panes shown for a process, in focus order.
+    pub fn of(messages: &MessageSettings) -> Vec<BlockType> {
+        match messages {
+            MessageSettings::None => vec![],
+            MessageSettings::Output => vec![BlockType::Out],
+            MessageSettings::Error => vec![BlockType::Err],
+            MessageSettings::All => vec![BlockType::Out, BlockType::Err],
+            MessageSettings::Combined => vec![BlockType::Combined],
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn label(self) -> &'static str {
+        match self {
+            BlockType::Main => "Main",
+            BlockType::Merged => "Merged",
+            BlockType::Out => "Out",
+            BlockType::Err => "Err",
+            BlockType::Combined => "Out + Err",
+        }
+    }
+}
+
+/// Fixed lines rendered above and below the scrolling content of a pane.
+#[derive(Clone, Default)]
+pub(crate) struct Decorations {
+    #[cfg(feature = "tui")]
+    pub header: Option<Line<'static>>,
+    #[cfg(feature = "tui")]
+    pub footer: Option<Line<'static>>,
+    /// Matches of the last search of `search_and_focus`, highlighted until `Esc` is pressed or
+    /// another search is focused.
+    pub highlight: Option<regex::Regex>,
+}
+
+impl PartialEq for Decorations {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "tui")]
+        if self.header != other.header || self.footer != other.footer {
+            return false;
+        }
+
+        self.highlight.as_ref().map(regex::Regex::as_str)
+            == other.highlight.as_ref().map(regex::Regex::as_str)
+    }
+}
+
+#[cfg(feature = "tui")]
+impl Decorations {
+    /// Empty lines don't reserve any row.
+    fn non_empty(line: Line<'static>) -> Option<Line<'static>> {
+        if line.width() == 0 {
+            None
+        } else {
+            Some(line)
+        }
+    }
+}
+
+pub struct Focus {
+    pub index: usize,
+    pub at: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            utils::{create_printing_process, Spawn},
+            CleanMode,
+        },
+        std::{collections::HashSet, io::Read, time::Instant},
+    };
+
+    #[cfg(feature = "tui")]
+    use crate::render::{focused_pane, DrawCache, Snapshots, PALETTE};
+
+    #[test]
+    fn line_tap_sees_every_process() {
+        let (sender, receiver) = channel();
+
+        TERMINAL.set_line_tap(move |process, stream, line| {
+            let _ = sender.send((process.to_string(), stream, line.to_string()));
+        });
+
+        TERMINAL
+            .add_process(
+                "TapOut",
+                create_printing_process(["foo"], 0.1, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        TERMINAL
+            .add_process(
+                "TapErr",
+                create_printing_process(["bar >&2"], 0.1, 1),
+                ProcessSettings::new(MessageSettings::Error),
+            )
+            .unwrap();
+
+        let mut seen = HashSet::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while seen.len() < 2 && Instant::now() < deadline {
+            // The tap is global, seeing the lines of the other tests too.
+            if let Ok(tapped) = receiver.recv_timeout(Duration::from_millis(100)) {
+                if tapped.0.starts_with("Tap") {
+                    seen.insert(tapped);
+                }
+            }
+        }
+
+        assert!(seen.contains(&("TapOut".to_string(), Stream::Out, "foo".to_string())));
+        assert!(seen.contains(&("TapErr".to_string(), Stream::Err, "bar".to_string())));
+    }
+
+    fn sent(lines: &[&str]) -> Receiver<String> {
+        let (sender, receiver) = channel();
+
+        for line in lines {
+            sender.send(line.to_string()).unwrap();
+        }
+
+        receiver
+    }
+
+    #[test]
+    fn lines_are_piped_to_the_command() {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let main_messages = SharedMessages::default();
+
+        thread_pipe(
+            "Piped".to_string(),
+            "cat".to_string(),
+            child.stdin.take().unwrap(),
+            sent(&["foo", "bar"]),
+            main_messages.clone(),
+        );
+
+        let mut output = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(output, "foo\nbar\n");
+        assert_eq!(main_messages.read_access().len(), 0);
+    }
+
+    #[test]
+    fn closed_pipe_stops_the_forwarding() {
+        let mut child = Command::new("true").stdin(Stdio::piped()).spawn().unwrap();
+        let stdin = child.stdin.take().unwrap();
+        child.wait().unwrap();
+
+        let main_messages = SharedMessages::default();
+
+        thread_pipe(
+            "Piped".to_string(),
+            "true".to_string(),
+            stdin,
+            sent(&["foo", "bar"]),
+            main_messages.clone(),
+        );
+
+        let notes = main_messages
+            .read_access()
+            .clone()
+            .into_iter()
+            .map(|message| message.text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            notes,
+            ["Pipe from 'Piped' to 'true' closed, stop forwarding."]
+        );
+    }
+
+    #[test]
+    fn restarted_child_is_written_into_the_slot() {
+        TERMINAL
+            .add_process(
+                "Restart",
+                create_printing_process(["foo"], 0.1, 0),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        // Shared with every clone of the process, like the one of a `ProcessHandle`.
+        let slot = TERMINAL.find_process("Restart").unwrap().child;
+
+        while slot.read_access().is_some() {
+            sleep(Duration::from_millis(10));
+        }
+
+        let child = Command::new("sleep")
+            .arg("5")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let pid = child.id();
+
+        TERMINAL.restart_process("Restart", child, false).unwrap();
+
+        let mut child = slot.write_with(|mut slot| slot.take()).unwrap();
+        assert_eq!(child.id(), pid);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn failed_exits_are_not_reported_as_ok() {
+        let status = |code: i32| {
+            Command::new("sh")
+                .args(["-c", &format!("exit {code}")])
+                .status()
+        };
+
+        assert_eq!(
+            exit_message("Exit", &status(0)),
+            "Process 'Exit' exited: ok: exit status: 0."
+        );
+        assert_eq!(
+            exit_message("Exit", &status(1)),
+            "Process 'Exit' exited: failed: exit status: 1."
+        );
+    }
+
+    #[test]
+    fn input_is_written_to_the_piped_stdin() {
+        let child = Command::new("sh")
+            .args(["-c", "read line; echo got $line"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        TERMINAL
+            .add_process(
+                "Stdin",
+                child,
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        TERMINAL.send_input("Stdin", "foo").unwrap();
+
+        let process = TERMINAL.find_process("Stdin").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        // The slot is emptied once the exit is reported, after the output is captured.
+        while process.child.read_access().is_some() && Instant::now() < deadline {
+            sleep(Duration::from_millis(10));
+        }
+
+        let lines = process
+            .out_messages
+            .read_access()
+            .clone()
+            .into_iter()
+            .map(|message| message.text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, ["got foo"]);
+
+        let error = TERMINAL.send_input("Stdin", "foo").unwrap_err();
+        assert_eq!(error.to_string(), "Process 'Stdin' has exited.");
+
+        TERMINAL
+            .add_process(
+                "NoStdin",
+                create_printing_process(["foo"], 0.1, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        let error = TERMINAL.send_input("NoStdin", "foo").unwrap_err();
+        assert_eq!(error.to_string(), "Process 'NoStdin' has no piped stdin.");
+    }
+
+    #[test]
+    fn search_ends_with_the_capture() {
+        TERMINAL
+            .add_process(
+                "Ended",
+                create_printing_process(["foo"], 0.1, 0),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        let Err(err) = TERMINAL.block_search_message("Ended", "bar") else {
+            panic!("'bar' is never printed");
+        };
+
+        assert_eq!(err.to_string(), "Search ended without a message.");
+    }
 
-        true
-    } else {
-        false
-    };
+    #[cfg(all(feature = "tui", not(feature = "clipboard")))]
+    #[test]
+    fn base64_follows_rfc_4648() {
+        for (bytes, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(bytes.as_bytes()), encoded);
+        }
+    }
 
-    let messages = messages
-        .into_iter()
-        .flat_map(|message| {
-            let messages = textwrap::wrap(&message, chunk.width.saturating_sub(3) as usize);
+    #[test]
+    fn combined_searches_find_captured_stderr_lines() {
+        let messages = SharedMessages::default();
 
-            let leading_spaces = messages
-                .first()
-                .map(|first_message| {
-                    " ".repeat(first_message.chars().take_while(|&c| c == ' ').count())
-                })
-                .unwrap_or_default();
+        messages.write_with(|mut messages| {
+            messages.push(Message::new("out".to_string(), Stream::Out));
+            messages.push(Message::new("err".to_string(), Stream::Err));
+        });
 
-            messages
-                .into_iter()
-                .enumerate()
-                .map(|(i, message)| {
-                    let mut message = message.into_owned();
+        let pattern = SearchPattern::Substring("err".to_string());
 
-                    if i != 0 {
-                        message.insert_str(0, &leading_spaces);
-                    }
+        assert_eq!(
+            find_message(&messages, &pattern, true),
+            Some(SearchHit {
+                index: 1,
+                line: "err".to_string()
+            })
+        );
+        assert_eq!(find_message(&messages, &pattern, false), None);
+    }
 
-                    Text::from(message)
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
+    #[test]
+    fn exited_processes_are_removed_after_the_delay() {
+        TERMINAL
+            .add_process(
+                "Removed",
+                Command::new("true")
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .unwrap(),
+                ProcessSettings::builder()
+                    .on_exit(OnExit::Remove(Duration::ZERO))
+                    .build(),
+            )
+            .unwrap();
 
-    let mut list = List::new(messages).block(block);
+        let start = Instant::now();
 
-    if is_scrolling {
-        list = list.highlight_style(Style::default().yellow().bold());
+        while TERMINAL.find_process("Removed").is_ok() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            sleep(Duration::from_millis(10));
+        }
     }
 
-    frame.render_stateful_widget(list, chunk, &mut state);
-}
+    #[test]
+    fn invalid_clear_pattern_fails_on_add() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
 
-fn sleep_thread() {
-    sleep(Duration::from_millis(50));
-}
+        let added = terminal.add_process(
+            "InvalidClear",
+            create_printing_process(["foo"], 0.1, 1),
+            ProcessSettings::builder()
+                .clear_regex(CleanMode::Custom("([".to_string()))
+                .build(),
+        );
 
-enum BlockType {
-    Main,
-    Out,
-    Err,
-}
+        assert!(added.is_err());
+        assert!(terminal.process_output("InvalidClear").is_err());
+    }
 
-enum BlockFocus {
-    Enter(usize),
-    Exit,
-}
+    #[test]
+    fn search_hit_has_the_line_index() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
 
-#[derive(Clone, PartialEq)]
-struct Process<
-    O = SharedMessages,
-    E = SharedMessages,
-    S = Shared<ScrollStatus>,
-    SM = Shared<Option<SearchMessage>>,
-> {
-    pub name: String,
-    pub out_messages: O,
-    pub err_messages: E,
-    pub settings: ProcessSettings,
-    pub scroll_status_out: S,
-    pub scroll_status_err: S,
-    pub search_message: SM,
-}
+        terminal
+            .add_process(
+                "SearchHit",
+                create_printing_process(["foo", "bar"], 0.05, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
 
-impl Process {
-    pub fn new(name: String, settings: ProcessSettings) -> Process {
-        Process {
-            name,
-            settings,
-            out_messages: Default::default(),
-            err_messages: Default::default(),
-            scroll_status_out: Default::default(),
-            scroll_status_err: Default::default(),
-            search_message: Default::default(),
+        let hit = terminal.block_search_hit("SearchHit", "bar").unwrap();
+
+        assert_eq!(hit.index, 1);
+        assert_eq!(hit.line, "bar");
+
+        // Found again among the captured lines.
+        assert_eq!(
+            terminal.try_search_hit("SearchHit", "bar").unwrap(),
+            Some(hit)
+        );
+    }
+
+    #[test]
+    fn search_and_focus_scrolls_to_the_hit() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        terminal
+            .add_process(
+                "Focus",
+                create_printing_process(["foo", "bar"], 0.05, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        terminal.wait_for_lines("Focus", 3, None).unwrap();
+
+        let hit = terminal.search_and_focus("Focus", "foo").unwrap();
+
+        assert_eq!(hit.index, 0);
+        assert_eq!(terminal.current_focus().as_deref(), Some("Focus"));
+
+        let process = terminal.find_process("Focus").unwrap();
+        assert_eq!(process.scroll_status_out.read_access().y, Some(1));
+    }
+
+    #[test]
+    fn concurrent_searches_on_one_process() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        terminal
+            .add_process(
+                "Searches",
+                create_printing_process(["foo", "bar"], 0.2, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        let (foo, bar) = std::thread::scope(|scope| {
+            let foo = scope.spawn(|| terminal.block_search_message("Searches", "foo"));
+            let bar = scope.spawn(|| terminal.block_search_message("Searches", "bar"));
+
+            (foo.join().unwrap(), bar.join().unwrap())
+        });
+
+        assert_eq!(foo.unwrap(), "foo");
+        assert_eq!(bar.unwrap(), "bar");
+    }
+
+    #[test]
+    fn failing_process_is_restarted_up_to_the_limit() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        let respawn = || Spawn::new("sh").args(["-c", "echo run; exit 1"]).spawn();
+
+        terminal
+            .add_process(
+                "Restarted",
+                respawn().unwrap(),
+                ProcessSettings::builder()
+                    .restart(RestartPolicy::UpTo(2))
+                    .respawn(respawn)
+                    .restart_delay(Duration::from_millis(10))
+                    .build(),
+            )
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while terminal.process_output("Restarted").unwrap().len() < 3 && Instant::now() < deadline {
+            sleep(Duration::from_millis(10));
         }
+
+        // Leave time for an unexpected fourth run.
+        sleep(Duration::from_millis(200));
+
+        assert_eq!(terminal.process_output("Restarted").unwrap(), ["run"; 3]);
+        assert!(terminal
+            .main_messages()
+            .iter()
+            .any(|message| message.contains("restarted (2 restarts)")));
     }
 
-    pub fn detach(&self) -> DetachProcess {
-        Process {
-            name: self.name.clone(),
-            settings: self.settings.clone(),
-            out_messages: self.out_messages.read_access().clone(),
-            err_messages: self.err_messages.read_access().clone(),
-            scroll_status_out: self.scroll_status_out.read_access().clone(),
-            scroll_status_err: self.scroll_status_err.read_access().clone(),
-            search_message: (),
+    #[test]
+    fn instances_are_independent() {
+        let first = Terminal::new_instance(TerminalSettings::default()).unwrap();
+        let second = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        first
+            .add_process(
+                "Instance",
+                create_printing_process(["foo"], 0.1, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        first.block_search_message("Instance", "foo").unwrap();
+
+        #[cfg(feature = "tui")]
+        assert!(first.render_to_string(80, 20).contains("foo"));
+        assert!(second.process_output("Instance").is_err());
+        assert!(TERMINAL.process_output("Instance").is_err());
+    }
+
+    #[test]
+    fn dropping_an_instance_stops_its_children() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        let handle = terminal
+            .add_process(
+                "Dropped",
+                Command::new("sleep")
+                    .arg("30")
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .unwrap(),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        drop(terminal);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while handle.is_running() && Instant::now() < deadline {
+            sleep(Duration::from_millis(10));
         }
+
+        assert!(!handle.is_running());
     }
-}
 
-#[derive(PartialEq)]
-struct SearchMessage {
-    pub submsg: String,
-    pub message: Option<String>,
-}
+    #[test]
+    fn wait_for_lines_counts_the_shown_pane() {
+        TERMINAL
+            .add_process(
+                "WaitErr",
+                create_printing_process(["foo >&2"], 0.1, 1),
+                ProcessSettings::new(MessageSettings::Error),
+            )
+            .unwrap();
 
-impl SearchMessage {
-    pub fn new(submsg: String) -> Self {
-        Self {
-            submsg,
-            message: None,
+        TERMINAL.wait_for_lines("WaitErr", 2, None).unwrap();
+
+        let err = TERMINAL.wait_for_lines("WaitErr", 100, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Output of 'WaitErr' ended after 10 of 100 lines."
+        );
+
+        TERMINAL
+            .add_process(
+                "WaitSlow",
+                create_printing_process(["foo"], 0.5, 5),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        let err = TERMINAL
+            .wait_for_lines("WaitSlow", 100, Some(Duration::from_millis(50)))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Timed out waiting for 100 lines of 'WaitSlow'"));
+    }
+
+    #[test]
+    fn scroll_to_moves_the_error_pane() {
+        let process = Process::new(
+            "ScrollErr".to_string(),
+            ProcessSettings::new(MessageSettings::Error),
+        );
+
+        process.err_messages.write_with(|mut messages| {
+            for line in ["foo", "bar", "baz"] {
+                messages.push(Message::new(line.to_string(), Stream::Err));
+            }
+        });
+
+        TERMINAL.attach(process.clone()).unwrap();
+        TERMINAL.scroll_to("ScrollErr", 0).unwrap();
+
+        assert_eq!(process.scroll_status_err.read_access().y, Some(1));
+        assert_eq!(process.scroll_status_out.read_access().y, None);
+    }
+
+    #[test]
+    fn scroll_survives_focus_changes() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        terminal
+            .add_process(
+                "Scroll",
+                create_printing_process(["foo", "bar"], 0.05, 1),
+                ProcessSettings::new(MessageSettings::All),
+            )
+            .unwrap();
+
+        terminal.wait_for_lines("Scroll", 3, None).unwrap();
+        terminal.scroll_to("Scroll", 0).unwrap();
+
+        terminal.set_focus(Some("Scroll")).unwrap();
+        terminal.set_focus(None).unwrap();
+
+        assert_eq!(terminal.get_scroll("Scroll").unwrap().y, Some(1));
+
+        let process = terminal.find_process("Scroll").unwrap();
+        assert_eq!(process.scroll_status_err.read_access().y, None);
+        assert_eq!(terminal.main_scroll.read_access().y, None);
+    }
+
+    #[test]
+    fn subscribers_receive_the_events() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+        let events = terminal.subscribe();
+
+        terminal
+            .add_process(
+                "Events",
+                create_printing_process(["foo"], 0.05, 1),
+                ProcessSettings::new(MessageSettings::Output),
+            )
+            .unwrap();
+
+        terminal.block_search_hit("Events", "foo").unwrap();
+        terminal.set_focus(Some("Events")).unwrap();
+        terminal.remove_process("Events").unwrap();
+
+        let received = events.try_iter().collect::<Vec<_>>();
+
+        assert_eq!(
+            received,
+            [
+                TerminalEvent::ProcessAdded("Events".to_string()),
+                TerminalEvent::SearchFound {
+                    process: "Events".to_string(),
+                    hit: SearchHit {
+                        index: 0,
+                        line: "foo".to_string(),
+                    },
+                },
+                TerminalEvent::FocusChanged(Some("Events".to_string())),
+                TerminalEvent::ProcessRemoved("Events".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn tabs_show_the_processes_of_their_group() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        for (name, group) in [("Web", "servers"), ("Db", "storage")] {
+            terminal
+                .add_process(
+                    name,
+                    create_printing_process(["foo"], 0.1, 1),
+                    ProcessSettings::builder().group(group).build(),
+                )
+                .unwrap();
         }
+
+        let screen = terminal.render_to_string(80, 20);
+        assert!(screen.contains("servers") && screen.contains("storage"));
+        assert!(screen.contains("Web") && !screen.contains("Db"));
+
+        cycle_group(&terminal.status.group, &terminal.processes, true);
+
+        let screen = terminal.render_to_string(80, 20);
+        assert!(screen.contains("Db") && !screen.contains("Web"));
     }
-}
 
-#[derive(Clone, PartialEq)]
-struct DrawCache<MM = SharedMessages, MS = BaseStatus, P = SharedProcesses> {
-    pub main_messages: MM,
-    pub main_scroll: MS,
-    pub processes: P,
-}
-
-impl DrawCache {
-    pub fn new(
-        main_messages: SharedMessages,
-        main_scroll: BaseStatus,
-        processes: SharedProcesses,
-    ) -> Self {
-        Self {
-            main_messages,
-            main_scroll,
-            processes,
+    #[cfg(feature = "tui")]
+    #[test]
+    fn merged_view_interleaves_the_processes() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        for name in ["Web", "Db"] {
+            terminal
+                .add_process(
+                    name,
+                    create_printing_process([name.to_lowercase().as_str()], 0.1, 1),
+                    ProcessSettings::new(MessageSettings::Output),
+                )
+                .unwrap();
         }
+
+        terminal.block_search_message("Web", "web").unwrap();
+        terminal.block_search_message("Db", "db").unwrap();
+
+        let merged = terminal.merged_messages();
+        assert!(merged.contains(&"Web | web".to_string()));
+        assert!(merged.contains(&"Db | db".to_string()));
+
+        assert!(!terminal.render_to_string(80, 20).contains("Merged"));
+
+        terminal.set_merged_view(true);
+
+        let screen = terminal.render_to_string(80, 20);
+        assert!(screen.contains("Merged") && screen.contains("Web | web"));
     }
 
-    pub fn default_detach() -> DrawCacheDetach {
-        DrawCache {
-            main_messages: Default::default(),
-            main_scroll: Default::default(),
-            processes: Default::default(),
+    #[cfg(feature = "tui")]
+    #[test]
+    fn panes_and_merged_lines_share_the_accent_of_their_process() {
+        let terminal = Terminal::new_instance(TerminalSettings {
+            auto_colors: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        for (name, messages) in [
+            ("Quiet", MessageSettings::None),
+            ("Web", MessageSettings::Output),
+            ("Db", MessageSettings::Output),
+        ] {
+            terminal
+                .add_process(
+                    name,
+                    create_printing_process([name.to_lowercase().as_str()], 0.1, 1),
+                    ProcessSettings::new(messages),
+                )
+                .unwrap();
         }
+
+        terminal.block_search_message("Db", "db").unwrap();
+
+        // Picked among the processes with panes, and kept once another one is removed.
+        terminal.remove_process("Web").unwrap();
+
+        let accent = terminal.find_process("Db").unwrap().accent;
+        assert_eq!(accent, PALETTE[1]);
+
+        let merged = terminal.taps.merged.read_access();
+        let line = merged
+            .iter()
+            .find(|message| message.text == "Db | db")
+            .unwrap();
+        assert_eq!(line.styles[0].1.fg, Some(accent));
     }
 
-    pub fn detach(&self) -> DrawCacheDetach {
-        DrawCache {
-            main_messages: self.main_messages.read_access().clone(),
-            main_scroll: self.main_scroll.detach(),
-            processes: self
-                .processes
-                .read_access()
-                .iter()
-                .map(Process::detach)
-                .collect::<Vec<_>>(),
+    #[cfg(feature = "tui")]
+    #[test]
+    fn main_section_keeps_max_main_lines() {
+        let terminal = Terminal::new_instance(TerminalSettings {
+            max_main_lines: Some(3),
+            ..Default::default()
+        })
+        .unwrap();
+
+        for index in 0..5 {
+            terminal.add_message(format!("line {index}"));
         }
+
+        assert_eq!(terminal.main_messages(), ["line 2", "line 3", "line 4"]);
+
+        // A view anchored to a dropped line starts from the oldest line kept.
+        terminal
+            .status
+            .main_scroll
+            .write_with(|mut scroll| scroll.y = Some(1));
+
+        let screen = terminal.render_to_string(80, 20);
+        assert!(screen.contains("line 2") && !screen.contains("line 1"));
     }
-}
 
-struct Regex(regex::Regex);
+    #[cfg(feature = "tui")]
+    #[test]
+    fn source_filter_shows_one_source() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        terminal.add_source_message("db", "connected");
+        terminal.add_message("listening");
+
+        terminal.set_source_filter(Some("db"));
+
+        let screen = terminal.render_to_string(80, 20);
+        assert!(screen.contains("connected") && !screen.contains("listening"));
+
+        terminal.set_source_filter(None);
 
-impl Regex {
-    pub fn new() -> Self {
-        Self(regex::Regex::new(r"\x1b\[([\x30-\x3f]*[\x20-\x2f]*[\x40-\x7e])").unwrap())
+        let screen = terminal.render_to_string(80, 20);
+        assert!(screen.contains("connected") && screen.contains("listening"));
     }
 
-    pub fn clear(&self, line: String) -> String {
-        self.0.replace_all(&line, "").to_string()
+    #[test]
+    fn only_focused_searches_are_highlighted() {
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        for name in ["First", "Second"] {
+            terminal
+                .add_process(
+                    name,
+                    create_printing_process(["foo", "bar"], 0.05, 1),
+                    ProcessSettings::new(MessageSettings::Output),
+                )
+                .unwrap();
+        }
+
+        let highlight = |name: &str| {
+            let process = terminal.find_process(name).unwrap();
+            let decorations = process.decorations.read_access();
+
+            decorations
+                .highlight
+                .as_ref()
+                .map(|highlight| highlight.as_str().to_string())
+        };
+
+        terminal.block_search_message("First", "foo").unwrap();
+        assert_eq!(highlight("First"), None);
+
+        terminal.search_and_focus("First", "foo").unwrap();
+        assert_eq!(highlight("First").as_deref(), Some("foo"));
+
+        terminal.search_and_focus("Second", "bar").unwrap();
+        assert_eq!(highlight("First"), None);
+        assert_eq!(highlight("Second").as_deref(), Some("bar"));
     }
-}
 
-pub struct Focus {
-    pub index: usize,
-    pub at: usize,
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_processes_are_captured_on_tasks() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        let spawn = || {
+            let _runtime = runtime.enter();
+
+            tokio::process::Command::new("sh")
+                .args(["-c", "echo foo; echo bar >&2; exit 3"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+        };
+
+        let settings = || ProcessSettings::new(MessageSettings::All);
+
+        // The tasks need a runtime to run on.
+        assert!(terminal
+            .add_process_async("Outside", spawn(), settings())
+            .is_err());
+
+        {
+            let _runtime = runtime.enter();
+
+            terminal
+                .add_process_async("Async", spawn(), settings())
+                .unwrap();
+        }
+
+        let process = terminal.find_process("Async").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while !(process.capture_ended() && process.exit_status.read_access().is_some())
+            && Instant::now() < deadline
+        {
+            sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(terminal.process_output("Async").unwrap(), ["foo"]);
+        assert_eq!(terminal.process_errors("Async").unwrap(), ["bar"]);
+        assert_eq!(
+            terminal
+                .process_exit_status("Async")
+                .unwrap()
+                .and_then(|status| status.code()),
+            Some(3)
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_capture_splits_carriage_returns_and_replaces_invalid_bytes() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        {
+            let _runtime = runtime.enter();
+
+            let child = tokio::process::Command::new("sh")
+                .args([
+                    "-c",
+                    "printf 'a\\377b\\n10%%\\r'; sleep 1; printf '100%%\\r'",
+                ])
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+
+            let settings = ProcessSettings::builder().carriage_return(true).build();
+
+            terminal
+                .add_process_async("Progress", child, settings)
+                .unwrap();
+        }
+
+        let process = terminal.find_process("Progress").unwrap();
+        let expected = |progress: &str| vec!["a\u{FFFD}b".to_string(), progress.to_string()];
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while terminal.process_output("Progress").unwrap() != expected("10%")
+            && Instant::now() < deadline
+        {
+            sleep(Duration::from_millis(10));
+        }
+
+        // Shown as soon as the `\r` is read, while the process is still running.
+        assert_eq!(
+            terminal.process_output("Progress").unwrap(),
+            expected("10%")
+        );
+        assert!(process.exit_status.read_access().is_none());
+
+        while !process.capture_ended() && Instant::now() < deadline {
+            sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(
+            terminal.process_output("Progress").unwrap(),
+            expected("100%")
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn running_async_processes_are_waited_for() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let terminal = Terminal::new_instance(TerminalSettings::default()).unwrap();
+
+        {
+            let _runtime = runtime.enter();
+
+            let child = tokio::process::Command::new("sh")
+                .args(["-c", "sleep 0.5; exit 4"])
+                .spawn()
+                .unwrap();
+
+            terminal
+                .add_process_async("Async", child, ProcessSettings::new(MessageSettings::None))
+                .unwrap();
+        }
+
+        // Not restarted over the running child.
+        let child = Command::new("true").spawn().unwrap();
+
+        let Err(err) = terminal.restart_process("Async", child, false) else {
+            panic!("The process is still running.");
+        };
+
+        assert_eq!(err.to_string(), "Process 'Async' is still running.");
+
+        let status = terminal.wait_process_exit("Async").unwrap();
+
+        assert_eq!(status.code(), Some(4));
+        assert!(!terminal.process_info("Async").unwrap().running);
+    }
+
+    #[cfg(feature = "tui")]
+    fn mixed_processes() -> Vec<Process> {
+        [
+            ("A", MessageSettings::All),
+            ("B", MessageSettings::Output),
+            ("C", MessageSettings::None),
+            ("D", MessageSettings::All),
+            ("E", MessageSettings::Error),
+            ("F", MessageSettings::Combined),
+        ]
+        .into_iter()
+        .map(|(name, messages)| Process::new(name.to_string(), ProcessSettings::new(messages)))
+        .collect()
+    }
+
+    /// Pane expected at each focus index of `mixed_processes`, starting from 1.
+    #[cfg(feature = "tui")]
+    const MIXED_PANES: [(&str, BlockType); 7] = [
+        ("A", BlockType::Out),
+        ("A", BlockType::Err),
+        ("B", BlockType::Out),
+        ("D", BlockType::Out),
+        ("D", BlockType::Err),
+        ("E", BlockType::Err),
+        ("F", BlockType::Combined),
+    ];
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn focus_maps_every_pane_of_mixed_processes() {
+        let processes = mixed_processes()
+            .iter()
+            .map(|process| {
+                process
+                    .try_detach(&mut Snapshots::default(), || true)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let resolve =
+            |focus| focused_pane(processes.clone(), focus).map(|(process, ty)| (process.name, ty));
+
+        for (index, (name, ty)) in MIXED_PANES.into_iter().enumerate() {
+            assert_eq!(resolve(index + 1), Some((name.to_string(), ty)));
+        }
+
+        assert_eq!(resolve(MIXED_PANES.len() + 1), None);
+        assert_eq!(count_panes(&mixed_processes()), MIXED_PANES.len());
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn focus_targets_the_same_pane_for_keys_and_drawing() {
+        let processes = mixed_processes();
+
+        let pane = ActionPane {
+            focus: Default::default(),
+            main_messages: Default::default(),
+            main_scroll: Default::default(),
+            merged: Default::default(),
+            merged_messages: Default::default(),
+            merged_scroll: Default::default(),
+            processes: Shared::new(processes.clone()),
+        };
+
+        for (index, (name, ty)) in MIXED_PANES.into_iter().enumerate() {
+            let process = processes
+                .iter()
+                .find(|process| process.name == name)
+                .unwrap();
+
+            let expected = match ty {
+                BlockType::Err => &process.err_messages,
+                _ => &process.out_messages,
+            };
+
+            let (_, messages) = focused_scroll(&pane, index + 1).unwrap();
+
+            assert!(
+                messages.ptr_eq(expected),
+                "focus {} is not {name}",
+                index + 1
+            );
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn full_screen_snapshots_only_the_focused_pane() {
+        let processes = mixed_processes();
+
+        for process in &processes {
+            for messages in [&process.out_messages, &process.err_messages] {
+                messages.write_with(|mut messages| {
+                    messages.push(Message::new(process.name.clone(), Stream::Out));
+                });
+            }
+        }
+
+        let status = BaseStatus::default();
+        let data = DrawCache::new(
+            Default::default(),
+            Default::default(),
+            status.clone(),
+            Shared::new(processes),
+        );
+
+        for (index, (name, ty)) in MIXED_PANES.into_iter().enumerate() {
+            status
+                .focus
+                .write_with(|mut focus| *focus = Some(index + 1));
+
+            let read = data.try_detach(&mut Snapshots::default()).unwrap();
+
+            let snapshotted = read
+                .processes
+                .iter()
+                .flat_map(|process| {
+                    [
+                        (&process.name, BlockType::Out, &process.out_messages),
+                        (&process.name, BlockType::Err, &process.err_messages),
+                    ]
+                })
+                .filter(|(_, _, messages)| messages.len() > 0)
+                .map(|(name, ty, _)| (name.as_str(), ty))
+                .collect::<Vec<_>>();
+
+            let ty = match ty {
+                BlockType::Combined => BlockType::Out,
+                ty => ty,
+            };
+
+            assert_eq!(snapshotted, vec![(name, ty)]);
+            assert_eq!(read.main_messages.len(), 0);
+        }
+    }
 }
@@ -1,24 +1,160 @@
-use std::process::{Child, Command, Stdio};
+use {
+    anyhow::{Context, Result},
+    std::{
+        ffi::OsStr,
+        path::Path,
+        process::{Child, Command, Stdio},
+    },
+};
 
-/// Create a process that prints messages and sleeps.
-pub fn create_printing_process<'a, const N: usize>(
+/// Create a process that prints messages and sleeps. A message ending with `>&2` is printed to
+/// stderr. It runs `sh` on Unix and PowerShell on Windows.
+pub fn create_printing_process<const N: usize>(
     messages: [&str; N],
     sleep: f64,
     last: u64,
 ) -> Child {
-    let mut args = format!("sleep {sleep}");
+    let repeats = (last as f64 / sleep / messages.len() as f64) as usize;
 
-    for _ in 0..(last as f64 / sleep / messages.len() as f64) as usize {
-        for message in messages {
-            args.push_str(&format!(" && echo {message} && sleep {sleep}"));
-        }
-    }
-
-    Command::new("sh")
-        .arg("-c")
-        .arg(args)
+    printing_command((0..repeats).flat_map(|_| messages), sleep)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .unwrap()
 }
+
+#[cfg(not(windows))]
+fn printing_command<'a>(messages: impl Iterator<Item = &'a str>, sleep: f64) -> Command {
+    let mut script = format!("sleep {sleep}");
+
+    for message in messages {
+        script.push_str(&format!(" && echo {message} && sleep {sleep}"));
+    }
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(script);
+    command
+}
+
+#[cfg(windows)]
+fn printing_command<'a>(messages: impl Iterator<Item = &'a str>, sleep: f64) -> Command {
+    let sleep = format!("Start-Sleep -Milliseconds {}", (sleep * 1000.0) as u64);
+    let mut script = sleep.clone();
+
+    for message in messages {
+        // The `sh` redirection to stderr, spelled the PowerShell way.
+        let (message, stream) = match message.strip_suffix(">&2") {
+            Some(message) => (message.trim_end(), "Error"),
+            None => (message, "Out"),
+        };
+
+        script.push_str(&format!(
+            "; [Console]::{stream}.WriteLine('{}'); {sleep}",
+            message.replace('\'', "''")
+        ));
+    }
+
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", script.as_str()]);
+    command
+}
+
+/// Spawn `program` with its stdout and stderr piped, as `add_process` expects, along with
+/// `envs` and the working directory `cwd`. Use `Spawn` to also pipe its stdin.
+pub fn spawn<I, A, E, K, V>(program: &str, args: I, envs: E, cwd: Option<&Path>) -> Result<Child>
+where
+    I: IntoIterator<Item = A>,
+    A: AsRef<OsStr>,
+    E: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let mut spawn = Spawn::new(program).args(args).envs(envs);
+
+    if let Some(cwd) = cwd {
+        spawn = spawn.current_dir(cwd);
+    }
+
+    spawn.spawn()
+}
+
+/// Builder of a child ready to be shown with `add_process`: its stdout and stderr are piped,
+/// and its stdin too with `with_stdin`, for `send_input`. Otherwise its stdin is closed, so it
+/// doesn't read the keys of the terminal.
+pub struct Spawn {
+    command: Command,
+    stdin: bool,
+}
+
+impl Spawn {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Self {
+            command: Command::new(program),
+            stdin: false,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Self {
+        self.command.arg(arg);
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.command.args(args);
+        self
+    }
+
+    pub fn env<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.command.env(key, value);
+        self
+    }
+
+    pub fn envs<I, K, V>(mut self, envs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.command.envs(envs);
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.command.current_dir(dir);
+        self
+    }
+
+    /// Pipe the stdin of the child, to write to it with `send_input`.
+    pub fn with_stdin(self) -> Self {
+        Self {
+            stdin: true,
+            ..self
+        }
+    }
+
+    pub fn spawn(mut self) -> Result<Child> {
+        self.command
+            .stdin(if self.stdin {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to spawn '{}'",
+                    self.command.get_program().to_string_lossy()
+                )
+            })
+    }
+}